@@ -0,0 +1 @@
+(self.webpackJsonp=self.webpackJsonp||[]).push([[0],{0:function(e,t,n){"use strict";e.exports={env:"production",api_host:"api-v2.soundcloud.com",client_id:"fixtureClientId0123456789abcdef",version:"1731673925"}}}]);
@@ -0,0 +1,479 @@
+//! Non-blocking counterpart of [`Client`](crate::Client), for callers already
+//! running on a tokio runtime.
+
+use std::{collections::VecDeque, future::Future, path::PathBuf};
+
+use futures_util::stream;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, ORIGIN, REFERER, RETRY_AFTER, USER_AGENT},
+    RequestBuilder, Response,
+};
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
+
+use crate::{
+    client::{save_client_id, USER_AGENT as USER_AGENT_VALUE},
+    endpoints::{
+        decode, find_client_id, script_urls, search_suggestions_url, search_url, track_url,
+        BaseUrls,
+    },
+    models::{
+        collections::Collection, media::Stream, resources::Transcoding,
+        suggestions::SearchSuggestion, tracks::Track,
+    },
+    retry::{RateLimiter, RetryPolicy},
+    search::{SearchFilters, SearchKind},
+    ClientBuilder,
+};
+
+/// Async client with the core of the [`Client`](crate::Client) surface,
+/// built with [`ClientBuilder::build_async`].
+///
+/// Shares the models, endpoints, `client_id` handling, retries and rate
+/// limiting with the blocking client.
+#[derive(Debug)]
+#[allow(clippy::struct_field_names)]
+pub struct AsyncClient {
+    http: reqwest::Client,
+    base: BaseUrls,
+    client_id: Mutex<Option<String>>,
+    client_id_file: Option<PathBuf>,
+    retry: RetryPolicy,
+    limiter: Option<RateLimiter>,
+}
+
+impl AsyncClient {
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be built, for example
+    /// because the TLS backend fails to initialize.
+    pub fn new() -> Result<Self, crate::Error> {
+        ClientBuilder::new().build_async()
+    }
+
+    pub(crate) fn from_builder(builder: ClientBuilder) -> Result<Self, crate::Error> {
+        let mut headers = HeaderMap::new();
+        if let Ok(origin) = HeaderValue::from_str(&builder.base.web) {
+            headers.insert(ORIGIN, origin.clone());
+            headers.insert(REFERER, origin);
+        }
+        headers.insert(USER_AGENT, HeaderValue::from_static(USER_AGENT_VALUE));
+
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .connect_timeout(builder.connect_timeout)
+            .read_timeout(builder.read_timeout)
+            .build()?;
+
+        Ok(Self {
+            http,
+            client_id: Mutex::new(builder.initial_client_id()),
+            client_id_file: builder.client_id_file,
+            retry: builder.retry,
+            limiter: builder.rate_limit.map(RateLimiter::new),
+            base: builder.base,
+        })
+    }
+
+    /// Returns the `client_id` in use, scraping one on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the web origin or its scripts cannot be fetched or
+    /// no `client_id` is found in them.
+    pub async fn client_id(&self) -> Result<String, crate::Error> {
+        let current = self.client_id.lock().await.clone();
+        match current {
+            Some(client_id) => Ok(client_id),
+            None => self.replace_client_id(None).await,
+        }
+    }
+
+    /// Scrapes a fresh `client_id`, replacing the one in use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the web origin or its scripts cannot be fetched or
+    /// no `client_id` is found in them.
+    pub async fn refresh_client_id(&self) -> Result<String, crate::Error> {
+        let current = self.client_id.lock().await.clone();
+        self.replace_client_id(current.as_deref()).await
+    }
+
+    /// Scrapes a new `client_id` unless another task already replaced
+    /// `stale`, saving it to the `client_id` file if there is one.
+    ///
+    /// The scrape runs without holding the lock, so requests with a working
+    /// `client_id` aren't held up by it.
+    async fn replace_client_id(&self, stale: Option<&str>) -> Result<String, crate::Error> {
+        let replaced = |client_id: &Option<String>| {
+            client_id
+                .as_deref()
+                .filter(|&current| Some(current) != stale)
+                .map(String::from)
+        };
+        if let Some(current) = replaced(&*self.client_id.lock().await) {
+            return Ok(current);
+        }
+
+        let fresh = self.retrying(|| self.scrape_client_id()).await?;
+        let mut client_id = self.client_id.lock().await;
+        // Another task may have scraped one meanwhile; keep the first.
+        if let Some(current) = replaced(&client_id) {
+            return Ok(current);
+        }
+        if let Some(path) = &self.client_id_file {
+            save_client_id(path, &fresh);
+        }
+        *client_id = Some(fresh.clone());
+        Ok(fresh)
+    }
+
+    async fn scrape_client_id(&self) -> Result<String, crate::Error> {
+        let page = self
+            .send(self.http.get(&self.base.web))
+            .await?
+            .text()
+            .await?;
+
+        for url in script_urls(&page, &self.base)? {
+            let Ok(res) = self.send(self.http.get(&url)).await else {
+                continue;
+            };
+            let Ok(script) = res.text().await else {
+                continue;
+            };
+            if let Some(client_id) = find_client_id(&script)? {
+                return Ok(client_id);
+            }
+        }
+
+        Err(crate::Error::ClientIdScrape {
+            url: self.base.web.clone(),
+        })
+    }
+
+    /// Runs `request` with the current `client_id`, and once more with a
+    /// freshly scraped one if the server rejects it as expired, with a 401 or
+    /// an api-v2 403.
+    async fn authorized<T, F, Fut>(&self, request: F) -> Result<T, crate::Error>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T, crate::Error>>,
+    {
+        let client_id = self.client_id().await?;
+        match self.retrying(|| request(client_id.clone())).await {
+            Err(err) if err.rejects_client_id(&self.base.api_v2) => {
+                let client_id = self.replace_client_id(Some(&client_id)).await?;
+                self.retrying(|| request(client_id.clone())).await
+            }
+            result => result,
+        }
+    }
+
+    /// Runs `request`, retrying retryable failures as the [`RetryPolicy`] allows.
+    async fn retrying<T, F, Fut>(&self, request: F) -> Result<T, crate::Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, crate::Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Err(err) => match self.retry.wait(attempt, &err) {
+                    Some(wait) => {
+                        tokio::time::sleep(wait).await;
+                        attempt += 1;
+                    }
+                    None => return Err(err),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends `request` once the rate limit allows, turning error statuses
+    /// into the matching [`crate::Error`].
+    async fn send(&self, request: RequestBuilder) -> Result<Response, crate::Error> {
+        if let Some(wait) = self.limiter.as_ref().and_then(RateLimiter::reserve) {
+            tokio::time::sleep(wait).await;
+        }
+
+        let res = request.send().await?;
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        let url = String::from(res.url().as_str());
+        let retry_after = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let body = res.text().await.unwrap_or_default();
+        Err(crate::Error::from_status(
+            status.as_u16(),
+            url,
+            retry_after.as_deref(),
+            || body,
+        ))
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, crate::Error> {
+        let res = self.send(request).await?;
+        let url = String::from(res.url().as_str());
+        let text = res.text().await?;
+        decode(url, &text)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response cannot be decoded.
+    pub async fn track(&self, id: i64) -> Result<Track, crate::Error> {
+        let url = track_url(&self.base, id);
+        self.authorized(|client_id| {
+            self.json(self.http.get(&url).query(&[("client_id", client_id)]))
+        })
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response cannot be decoded.
+    pub async fn stream(&self, transcoding: &Transcoding) -> Result<Stream, crate::Error> {
+        let url = transcoding.url();
+        self.authorized(|client_id| {
+            self.json(self.http.get(&url).query(&[("client_id", client_id)]))
+        })
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the body cannot be read.
+    pub async fn bytes(&self, url: &str) -> Result<Vec<u8>, crate::Error> {
+        self.retrying(|| async {
+            let res = self.send(self.http.get(url)).await?;
+            Ok(res.bytes().await?.to_vec())
+        })
+        .await
+    }
+
+    /// Searches tracks, users and playlists alike, without filters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response cannot be decoded.
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Collection, crate::Error> {
+        self.search_with(
+            query,
+            SearchKind::All,
+            &SearchFilters::default(),
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    /// Searches for resources of `kind` matching `filters`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response cannot be decoded.
+    pub async fn search_with(
+        &self,
+        query: &str,
+        kind: SearchKind,
+        filters: &SearchFilters,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Collection, crate::Error> {
+        let url = search_url(&self.base, kind);
+        let (limit, offset) = (limit.to_string(), offset.to_string());
+        let filters = filters.query_pairs();
+        self.authorized(|client_id| {
+            self.json(
+                self.http
+                    .get(&url)
+                    .query(&[
+                        ("client_id", client_id.as_str()),
+                        ("q", query),
+                        ("limit", &limit),
+                        ("offset", &offset),
+                    ])
+                    .query(&filters),
+            )
+        })
+        .await
+    }
+
+    /// Completions of a partly typed `query`, most popular first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response cannot be decoded.
+    pub async fn search_suggestions(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> Result<Collection<SearchSuggestion>, crate::Error> {
+        let url = search_suggestions_url(&self.base);
+        let limit = limit.to_string();
+        self.authorized(|client_id| {
+            self.json(self.http.get(&url).query(&[
+                ("client_id", client_id.as_str()),
+                ("q", query),
+                ("limit", &limit),
+            ]))
+        })
+        .await
+    }
+
+    /// Fetches the page following `collection`, or `None` on the last page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response cannot be decoded.
+    pub async fn next_collection<T: DeserializeOwned>(
+        &self,
+        collection: &Collection<T>,
+    ) -> Result<Option<Collection<T>>, crate::Error> {
+        let Some(next_href) = collection.next_href() else {
+            return Ok(None);
+        };
+        self.next_page(&next_href).await.map(Some)
+    }
+
+    /// Fetches the page behind a `next_href`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response cannot be decoded.
+    pub async fn next_page<T: DeserializeOwned>(
+        &self,
+        next_href: &str,
+    ) -> Result<Collection<T>, crate::Error> {
+        self.authorized(|client_id| {
+            self.json(self.http.get(next_href).query(&[("client_id", client_id)]))
+        })
+        .await
+    }
+
+    /// Streams the items of `first` and every page after it, fetching each
+    /// page once the previous one is used up. Ends after the last page or
+    /// after the first error; use `StreamExt::take` to cap the item count.
+    pub fn paginate<'a, T: DeserializeOwned + 'a>(
+        &'a self,
+        first: Collection<T>,
+    ) -> impl futures_util::Stream<Item = Result<T, crate::Error>> + 'a {
+        let next_href = first.next_href();
+        let items: VecDeque<T> = first.into_collection().into();
+
+        stream::unfold(
+            (items, next_href, false),
+            move |(mut items, mut next_href, done)| async move {
+                if done {
+                    return None;
+                }
+                while items.is_empty() {
+                    match self.next_page::<T>(&next_href.take()?).await {
+                        Ok(page) => {
+                            next_href = page.next_href();
+                            items = page.into_collection().into();
+                        }
+                        Err(err) => return Some((Err(err), (items, None, true))),
+                    }
+                }
+                let item = items.pop_front()?;
+                Some((Ok(item), (items, next_href, false)))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use crate::{
+        models::resources::Resource,
+        testing::{FixtureServer, CLIENT_ID},
+    };
+
+    const TRACK_ID: i64 = 1_126_821_928; // BIG SHOT - Toby Fox
+
+    #[tokio::test]
+    async fn test_fixture_track_stream_bytes() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.async_client()?;
+
+        let track = client.track(TRACK_ID).await?;
+        let transcoding = track.media().unwrap().progressive().unwrap();
+        let stream = client.stream(&transcoding).await?;
+        let stream_bytes = client.bytes(&stream.url()).await?;
+
+        assert_eq!(client.client_id().await?, CLIENT_ID);
+        assert_eq!(track.title().unwrap(), String::from("BIG SHOT"));
+        assert_eq!(
+            stream_bytes,
+            include_bytes!("../fixtures/track.mp3").to_vec()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fixture_paginate_stream() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.async_client()?;
+
+        let first = client.search("undertale", 50, 0).await?;
+        let resources: Vec<Resource> = client
+            .paginate(first)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+        assert_eq!(resources.len(), 5);
+
+        let first = client.search("unavailable", 50, 0).await?;
+        let results: Vec<_> = client.paginate::<Resource>(first).collect().await;
+        assert_eq!(results.len(), 4);
+        assert!(results[3].is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fixture_stale_client_id_refreshed() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server
+            .builder()
+            .client_id("expiredClientId")
+            .build_async()?;
+
+        let track = client.track(TRACK_ID).await?;
+
+        assert_eq!(track.title().unwrap(), String::from("BIG SHOT"));
+        assert_eq!(client.client_id().await?, CLIENT_ID);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fixture_missing_track() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.async_client()?;
+
+        let err = client.track(1).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{err:?}");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,468 @@
+//! Size-bounded on-disk cache of downloaded audio, evicting least recently
+//! used entries first.
+//!
+//! Each entry is one file holding a small header (magic, length, CRC-32)
+//! followed by the audio bytes, so truncated or corrupted files are detected
+//! and dropped instead of being handed to the decoder.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+const MAGIC: &[u8; 4] = b"ESTC";
+const HEADER_LEN: usize = 16;
+const EXTENSION: &str = "audio";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Identifies a cached file by track and transcoding preset.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    track_id: i64,
+    preset: String,
+}
+
+impl CacheKey {
+    /// Characters outside `[A-Za-z0-9_]` in `preset` are replaced by `_`.
+    #[must_use]
+    pub fn new(track_id: i64, preset: &str) -> Self {
+        let preset = preset
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        Self { track_id, preset }
+    }
+
+    #[must_use]
+    pub fn track_id(&self) -> i64 {
+        self.track_id
+    }
+
+    #[must_use]
+    pub fn preset(&self) -> String {
+        self.preset.clone()
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}-{}.{EXTENSION}", self.track_id, self.preset)
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        if path.extension()? != EXTENSION {
+            return None;
+        }
+        let (track_id, preset) = path.file_stem()?.to_str()?.split_once('-')?;
+        Some(Self {
+            track_id: track_id.parse().ok()?,
+            preset: String::from(preset),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+    pub budget: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Entries dropped because their contents failed the integrity check.
+    pub corrupted: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    size: u64,
+    last_used: SystemTime,
+}
+
+#[derive(Debug)]
+pub struct AudioCache {
+    dir: PathBuf,
+    entries: HashMap<CacheKey, Entry>,
+    stats: CacheStats,
+}
+
+impl AudioCache {
+    /// Opens or creates the cache in `dir`, evicting down to `budget` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dir` cannot be created or listed.
+    pub fn open(dir: impl Into<PathBuf>, budget: u64) -> Result<Self, crate::Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut entries = HashMap::new();
+        for dir_entry in fs::read_dir(&dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|ext| ext == TEMP_EXTENSION) {
+                // Left over from a write that never finished.
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            let Some(key) = CacheKey::from_path(&path) else {
+                continue;
+            };
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            entries.insert(
+                key,
+                Entry {
+                    size: metadata.len(),
+                    last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                },
+            );
+        }
+
+        let mut cache = Self {
+            dir,
+            entries,
+            stats: CacheStats {
+                budget,
+                ..CacheStats::default()
+            },
+        };
+        cache.evict(0)?;
+        Ok(cache)
+    }
+
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    #[must_use]
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the cached bytes for `key`, dropping the entry if it is corrupt.
+    pub fn get(&mut self, key: &CacheKey) -> Option<Vec<u8>> {
+        if !self.entries.contains_key(key) {
+            self.stats.misses += 1;
+            return None;
+        }
+
+        let path = self.dir.join(key.file_name());
+        let Some(bytes) = read_entry(&path) else {
+            self.stats.misses += 1;
+            self.stats.corrupted += 1;
+            let _ = self.remove(key);
+            return None;
+        };
+
+        self.stats.hits += 1;
+        self.touch(key, &path);
+        Some(bytes)
+    }
+
+    /// Returns the cached bytes of track `track_id` in whichever preset was
+    /// used most recently, so a track plays without first looking it up.
+    pub fn get_track(&mut self, track_id: i64) -> Option<Vec<u8>> {
+        let mut keys: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(key, _)| key.track_id == track_id)
+            .map(|(key, entry)| (key.clone(), entry.last_used))
+            .collect();
+        keys.sort_by(|(_, a), (_, b)| b.cmp(a));
+        if keys.is_empty() {
+            self.stats.misses += 1;
+        }
+        keys.into_iter().find_map(|(key, _)| self.get(&key))
+    }
+
+    /// Stores `bytes` under `key`, evicting older entries to stay in budget.
+    /// Entries larger than the whole budget are not stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry cannot be written or old ones removed.
+    pub fn put(&mut self, key: CacheKey, bytes: &[u8]) -> Result<(), crate::Error> {
+        let size = (HEADER_LEN + bytes.len()) as u64;
+        if size > self.stats.budget {
+            return Ok(());
+        }
+        self.remove(&key)?;
+        self.evict(size)?;
+
+        let path = self.dir.join(key.file_name());
+        let temp = path.with_extension(TEMP_EXTENSION);
+        {
+            let mut file = File::create(&temp)?;
+            file.write_all(MAGIC)?;
+            file.write_all(&(bytes.len() as u64).to_le_bytes())?;
+            file.write_all(&crc32fast::hash(bytes).to_le_bytes())?;
+            file.write_all(bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temp, &path)?;
+
+        self.entries.insert(
+            key,
+            Entry {
+                size,
+                last_used: SystemTime::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the entry's file exists but cannot be removed.
+    pub fn remove(&mut self, key: &CacheKey) -> Result<(), crate::Error> {
+        if self.entries.remove(key).is_none() {
+            return Ok(());
+        }
+        match fs::remove_file(self.dir.join(key.file_name())) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if an entry's file cannot be removed.
+    pub fn clear(&mut self) -> Result<(), crate::Error> {
+        let keys: Vec<CacheKey> = self.entries.keys().cloned().collect();
+        for key in keys {
+            self.remove(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Changes the byte budget, evicting entries if it shrank.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an evicted entry's file cannot be removed.
+    pub fn set_budget(&mut self, budget: u64) -> Result<(), crate::Error> {
+        self.stats.budget = budget;
+        self.evict(0)
+    }
+
+    /// Checks every entry's integrity, dropping corrupt ones, and returns how
+    /// many were dropped.
+    pub fn verify(&mut self) -> usize {
+        let corrupt: Vec<CacheKey> = self
+            .entries
+            .keys()
+            .filter(|key| read_entry(&self.dir.join(key.file_name())).is_none())
+            .cloned()
+            .collect();
+        for key in &corrupt {
+            let _ = self.remove(key);
+        }
+        self.stats.corrupted += corrupt.len() as u64;
+        corrupt.len()
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes(),
+            ..self.stats
+        }
+    }
+
+    fn bytes(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// Evicts least recently used entries until `incoming` more bytes fit.
+    fn evict(&mut self, incoming: u64) -> Result<(), crate::Error> {
+        let mut bytes = self.bytes();
+        while bytes + incoming > self.stats.budget {
+            let Some((key, entry)) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, entry)| (key.clone(), *entry))
+            else {
+                break;
+            };
+            self.remove(&key)?;
+            self.stats.evictions += 1;
+            bytes -= entry.size;
+        }
+        Ok(())
+    }
+
+    /// Marks `key` as just used, on disk too so the order survives restarts.
+    fn touch(&mut self, key: &CacheKey, path: &Path) {
+        let now = SystemTime::now();
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.last_used = now;
+        }
+        if let Ok(file) = File::options().write(true).open(path) {
+            let _ = file.set_modified(now);
+        }
+    }
+}
+
+/// Reads an entry's audio bytes, or `None` if the file is missing or corrupt.
+fn read_entry(path: &Path) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let mut header = [0; HEADER_LEN];
+    file.read_exact(&mut header).ok()?;
+    if &header[..4] != MAGIC {
+        return None;
+    }
+    let len = u64::from_le_bytes(header[4..12].try_into().ok()?);
+    let crc = u32::from_le_bytes(header[12..16].try_into().ok()?);
+
+    // A corrupt length mustn't size the buffer, so check it against the file.
+    let body = file.metadata().ok()?.len().checked_sub(HEADER_LEN as u64)?;
+    if len != body {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(usize::try_from(len).ok()?);
+    file.read_to_end(&mut bytes).ok()?;
+    if bytes.len() as u64 != len || crc32fast::hash(&bytes) != crc {
+        return None;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::{AudioCache, CacheKey, HEADER_LEN};
+
+    fn key(track_id: i64) -> CacheKey {
+        CacheKey::new(track_id, "mp3_1_0")
+    }
+
+    #[test]
+    fn test_put_get_persists_across_open() -> Result<(), crate::Error> {
+        let dir = TempDir::new()?;
+
+        let mut cache = AudioCache::open(dir.path(), 1024)?;
+        cache.put(key(1), b"big shot")?;
+        assert_eq!(cache.get(&key(1)).unwrap(), b"big shot");
+        assert!(cache.get(&key(2)).is_none());
+
+        let mut cache = AudioCache::open(dir.path(), 1024)?;
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.bytes, (HEADER_LEN + 8) as u64);
+        assert_eq!(cache.get(&key(1)).unwrap(), b"big shot");
+        assert_eq!(cache.stats().hits, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_track_in_any_preset() -> Result<(), crate::Error> {
+        let dir = TempDir::new()?;
+
+        let mut cache = AudioCache::open(dir.path(), 1024)?;
+        cache.put(CacheKey::new(1, "aac_1_0"), b"aac")?;
+        cache.put(key(1), b"mp3")?;
+        cache.put(key(2), b"other")?;
+
+        assert_eq!(cache.get_track(1).unwrap(), b"mp3");
+        cache.get(&CacheKey::new(1, "aac_1_0"));
+        assert_eq!(cache.get_track(1).unwrap(), b"aac");
+        assert!(cache.get_track(3).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_by_size() -> Result<(), crate::Error> {
+        let dir = TempDir::new()?;
+        let entry = [0; 40];
+        let budget = 3 * (HEADER_LEN + entry.len()) as u64;
+
+        let mut cache = AudioCache::open(dir.path(), budget)?;
+        cache.put(key(1), &entry)?;
+        cache.put(key(2), &entry)?;
+        cache.put(key(3), &entry)?;
+        assert!(cache.get(&key(1)).is_some());
+        cache.put(key(4), &entry)?;
+
+        assert!(cache.contains(&key(1)));
+        assert!(!cache.contains(&key(2)));
+        assert!(cache.contains(&key(4)));
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.stats().bytes <= budget);
+
+        cache.set_budget(budget / 3)?;
+        assert_eq!(cache.stats().entries, 1);
+        assert!(cache.contains(&key(4)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_skips_entries_over_budget() -> Result<(), crate::Error> {
+        let dir = TempDir::new()?;
+
+        let mut cache = AudioCache::open(dir.path(), 32)?;
+        cache.put(key(1), &[0; 64])?;
+
+        assert!(!cache.contains(&key(1)));
+        assert_eq!(cache.stats().bytes, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_drops_corrupt_entries() -> Result<(), crate::Error> {
+        let dir = TempDir::new()?;
+
+        let mut cache = AudioCache::open(dir.path(), 1024)?;
+        cache.put(key(1), b"big shot")?;
+        cache.put(key(2), b"the world revolving")?;
+        let path = dir.path().join("1-mp3_1_0.audio");
+        let mut bytes = fs::read(&path)?;
+        *bytes.last_mut().unwrap() ^= 0xff;
+        fs::write(&path, bytes)?;
+        fs::write(dir.path().join("2-mp3_1_0.audio"), b"ESTC")?;
+
+        assert!(cache.get(&key(1)).is_none());
+        assert!(!path.exists());
+        assert_eq!(cache.verify(), 1);
+        assert_eq!(cache.stats().corrupted, 2);
+        assert_eq!(cache.stats().entries, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_drops_entries_with_corrupt_length() -> Result<(), crate::Error> {
+        let dir = TempDir::new()?;
+
+        let mut cache = AudioCache::open(dir.path(), 1024)?;
+        cache.put(key(1), b"big shot")?;
+        let path = dir.path().join("1-mp3_1_0.audio");
+        let mut bytes = fs::read(&path)?;
+        bytes[4..12].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&path, bytes)?;
+
+        assert!(cache.get(&key(1)).is_none());
+        assert_eq!(cache.stats().corrupted, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_sanitizes_preset() {
+        let key = CacheKey::new(7, "../hls/opus");
+
+        assert_eq!(key.preset(), "___hls_opus");
+        assert_eq!(key.file_name(), "7-___hls_opus.audio");
+    }
+}
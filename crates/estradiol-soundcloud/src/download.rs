@@ -0,0 +1,452 @@
+//! Saving tracks to disk under templated, filesystem-safe names, resuming
+//! whatever an interrupted download left behind.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use ureq::Agent;
+
+use crate::{
+    endpoints::call,
+    models::{resources::Transcoding, tracks::Track},
+    Client,
+};
+
+/// Bytes read from the response before each write and progress report.
+const BUFFER_LEN: usize = 64 * 1024;
+/// Longest file name, in bytes, a template renders to; most filesystems cap
+/// names at 255 bytes.
+const MAX_NAME_LEN: usize = 200;
+
+/// How far along a download is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Bytes written so far, including any resumed from an earlier attempt.
+    pub downloaded: u64,
+    /// Size of the whole file, when the server reports it.
+    pub total: Option<u64>,
+    /// Segments written so far and in total, for HLS downloads.
+    pub segments: Option<(usize, usize)>,
+}
+
+impl DownloadProgress {
+    /// Fraction of the download done, from `0.0` to `1.0`, if it is known.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fraction(&self) -> Option<f32> {
+        match (self.total, self.segments) {
+            (Some(total), _) if total > 0 => Some(self.downloaded as f32 / total as f32),
+            (_, Some((done, total))) if total > 0 => Some(done as f32 / total as f32),
+            _ => None,
+        }
+    }
+}
+
+/// A track saved to disk.
+#[derive(Debug)]
+pub struct Download {
+    pub path: PathBuf,
+    /// Why the file could not be tagged, if it couldn't. The audio is saved
+    /// either way.
+    pub tag_error: Option<crate::Error>,
+}
+
+/// File name pattern for downloads, with `{artist}`, `{title}`, `{id}`,
+/// `{preset}` and `{ext}` placeholders.
+///
+/// Values substituted in are sanitized, so a `/` in a title does not turn
+/// into a directory, but the template itself may contain subdirectories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilenameTemplate {
+    template: String,
+}
+
+impl FilenameTemplate {
+    pub const DEFAULT: &'static str = "{artist} - {title}{ext}";
+
+    #[must_use]
+    pub fn new(template: &str) -> Self {
+        Self {
+            template: String::from(template),
+        }
+    }
+
+    /// The relative path `track` is saved under when downloaded as `transcoding`.
+    #[must_use]
+    pub fn render(&self, track: &Track, transcoding: &Transcoding) -> PathBuf {
+        let artist = track
+            .user()
+            .and_then(|user| user.username())
+            .unwrap_or_default();
+        let title = track.title().unwrap_or_default();
+        let rendered = self
+            .template
+            .replace("{artist}", &sanitize(&artist))
+            .replace("{title}", &sanitize(&title))
+            .replace("{id}", &track.id().to_string())
+            .replace("{preset}", &sanitize(&transcoding.preset()))
+            .replace("{ext}", &transcoding.format().file_extension().to_string());
+
+        rendered
+            .split(['/', '\\'])
+            .filter(|component| !component.is_empty())
+            .map(sanitize_component)
+            .collect()
+    }
+}
+
+impl Default for FilenameTemplate {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT)
+    }
+}
+
+/// Replaces characters that are invalid in file names on common platforms.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Makes one path component safe to create: no reserved names, no trailing
+/// dots or spaces, no `..`, and at most [`MAX_NAME_LEN`] bytes.
+fn sanitize_component(component: &str) -> String {
+    let mut name = sanitize(component);
+    if name.len() > MAX_NAME_LEN {
+        // Keep the extension, which is what players go by.
+        let ext = name
+            .rfind('.')
+            .filter(|&dot| name.len() - dot <= 8)
+            .map(|dot| name.split_off(dot))
+            .unwrap_or_default();
+        let end = (0..=MAX_NAME_LEN - ext.len())
+            .rev()
+            .find(|&i| name.is_char_boundary(i))
+            .unwrap_or_default();
+        name.truncate(end);
+        name.push_str(&ext);
+    }
+
+    let mut name = String::from(name.trim().trim_end_matches('.'));
+    let stem = name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let reserved = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.ends_with(|c: char| c.is_ascii_digit()));
+    if name.is_empty() || reserved {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Where a download is written until it is complete.
+pub(crate) fn part_path(path: &Path) -> PathBuf {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Records how many HLS segments of a `.part` file are complete, and how many
+/// bytes they take up, so a resumed download can drop a half-written one.
+fn segments_path(part: &Path) -> PathBuf {
+    let mut segments = part.as_os_str().to_owned();
+    segments.push(".segments");
+    PathBuf::from(segments)
+}
+
+/// Downloads `url` into `part`, continuing from wherever an earlier attempt
+/// stopped if the server honors range requests.
+pub(crate) fn progressive(
+    agent: &Agent,
+    url: &str,
+    part: &Path,
+    progress: &dyn Fn(DownloadProgress),
+) -> Result<(), crate::Error> {
+    let offset = std::fs::metadata(part).map_or(0, |metadata| metadata.len());
+    let mut request = agent.get(url);
+    if offset > 0 {
+        request = request.set("Range", &format!("bytes={offset}-"));
+    }
+    let res = match call(request) {
+        // The part file already holds everything there is.
+        Err(crate::Error::Status { status: 416, .. }) if offset > 0 => return Ok(()),
+        res => res?,
+    };
+
+    let (mut file, mut downloaded) = if res.status() == 206 {
+        (OpenOptions::new().append(true).open(part)?, offset)
+    } else {
+        (File::create(part)?, 0)
+    };
+    let total = res
+        .header("Content-Range")
+        .and_then(|range| range.rsplit_once('/'))
+        .and_then(|(_, total)| total.parse::<u64>().ok())
+        .or_else(|| {
+            res.header("Content-Length")
+                .and_then(|len| len.parse::<u64>().ok())
+        });
+
+    let mut reader = res.into_reader();
+    let mut buffer = vec![0; BUFFER_LEN];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        downloaded += read as u64;
+        progress(DownloadProgress {
+            downloaded,
+            total,
+            segments: None,
+        });
+    }
+    file.flush()?;
+
+    if total.is_some_and(|total| downloaded < total) {
+        // Retryable, and the retry picks up from here.
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(())
+}
+
+/// Downloads HLS `urls` one after another into `part`, skipping segments an
+/// earlier attempt finished.
+pub(crate) fn hls(
+    client: &Client,
+    urls: &[String],
+    part: &Path,
+    progress: &dyn Fn(DownloadProgress),
+) -> Result<(), crate::Error> {
+    let segments_path = segments_path(part);
+    let (done, mut downloaded) = std::fs::read_to_string(&segments_path)
+        .ok()
+        .and_then(|saved| {
+            let (done, len) = saved.trim().split_once(' ')?;
+            Some((done.parse::<usize>().ok()?, len.parse::<u64>().ok()?))
+        })
+        .unwrap_or((0, 0));
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(part)?;
+    file.set_len(downloaded)?;
+    file.seek(SeekFrom::End(0))?;
+
+    for (index, url) in urls.iter().enumerate().skip(done) {
+        let bytes = client.bytes(url)?;
+        file.write_all(&bytes)?;
+        file.flush()?;
+        downloaded += bytes.len() as u64;
+        std::fs::write(&segments_path, format!("{} {downloaded}", index + 1))?;
+        progress(DownloadProgress {
+            downloaded,
+            total: None,
+            segments: Some((index + 1, urls.len())),
+        });
+    }
+
+    let _ = std::fs::remove_file(&segments_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        sync::Mutex,
+    };
+
+    use id3::TagLike;
+
+    use crate::{
+        models::resources::{FileExtension, TranscodingKind, TranscodingPolicy},
+        testing::{mp3_segments, soundcloud_fixtures, FixtureServer, Reply, ARTWORK},
+    };
+
+    use super::{part_path, sanitize_component, segments_path, FilenameTemplate};
+
+    const TRACK_ID: i64 = 1_126_821_928; // BIG SHOT - Toby Fox
+    const HLS_TRACK_ID: i64 = 1_126_821_941; // THE WORLD REVOLVING - Toby Fox
+
+    #[test]
+    fn test_sanitize_component() {
+        assert_eq!(sanitize_component("a: b?"), "a_ b_");
+        assert_eq!(sanitize_component("name. "), "name");
+        assert_eq!(sanitize_component("con.mp3"), "_con.mp3");
+        assert_eq!(sanitize_component("COM1"), "_COM1");
+        assert_eq!(sanitize_component(".."), "_");
+
+        let long = sanitize_component(&format!("{}.mp3", "é".repeat(150)));
+        assert!(long.len() <= 200);
+        assert!(long.ends_with("é.mp3"));
+    }
+
+    #[test]
+    fn test_template_render() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+        let track = client.track(HLS_TRACK_ID)?;
+        let policy = TranscodingPolicy::new(vec![TranscodingKind::HlsOpus]);
+        let transcoding = track.transcoding(&policy)?;
+
+        assert_eq!(transcoding.format().file_extension(), FileExtension::OPUS);
+        assert_eq!(
+            FilenameTemplate::default().render(&track, &transcoding),
+            PathBuf::from("Toby Fox - THE WORLD REVOLVING.opus")
+        );
+        assert_eq!(
+            FilenameTemplate::new("{artist}/{id} {title}{ext}").render(&track, &transcoding),
+            Path::new("Toby Fox").join("1126821941 THE WORLD REVOLVING.opus")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_progressive_resumes() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let track = client.track(TRACK_ID)?;
+        let expected = include_bytes!("../fixtures/track.mp3");
+
+        let path = dir.path().join("Toby Fox - BIG SHOT.mp3");
+        std::fs::write(part_path(&path), &expected[..1000])?;
+
+        let reports = Mutex::new(Vec::new());
+        let downloaded = client.download_with(
+            &track,
+            dir.path(),
+            &TranscodingPolicy::default(),
+            &FilenameTemplate::default(),
+            |progress| reports.lock().unwrap().push(progress),
+        )?;
+
+        assert_eq!(downloaded.path, path);
+        assert!(downloaded.tag_error.is_none());
+        // Past the fixture's empty tag header, which tagging replaced.
+        assert!(std::fs::read(&path)?.ends_with(&expected[10..]));
+        assert!(!part_path(&path).exists());
+        let reports = reports.into_inner().unwrap();
+        assert!(reports.iter().all(|progress| progress.downloaded > 1000));
+        assert_eq!(reports.last().unwrap().fraction(), Some(1.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_hls_resumes_after_last_whole_segment() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let track = client.track(HLS_TRACK_ID)?;
+        let segments = mp3_segments();
+
+        let path = dir.path().join("Toby Fox - THE WORLD REVOLVING.mp3");
+        let part = part_path(&path);
+        // One whole segment, then half of the next one.
+        let mut partial = segments[0].to_vec();
+        partial.extend_from_slice(&segments[1][..10]);
+        std::fs::write(&part, partial)?;
+        std::fs::write(segments_path(&part), format!("1 {}", segments[0].len()))?;
+
+        let reports = Mutex::new(Vec::new());
+        client.download_with(
+            &track,
+            dir.path(),
+            &TranscodingPolicy::default(),
+            &FilenameTemplate::default(),
+            |progress| reports.lock().unwrap().push(progress.segments),
+        )?;
+
+        assert!(std::fs::read(&path)?.ends_with(&segments.concat()[10..]));
+        assert!(!segments_path(&part).exists());
+        assert_eq!(
+            reports.into_inner().unwrap(),
+            vec![Some((2, 3)), Some((3, 3))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_is_tagged() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let track = client.track(TRACK_ID)?;
+
+        let path = client
+            .download(&track, dir.path(), &TranscodingPolicy::default())?
+            .path;
+
+        let tag = id3::Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.title(), Some("BIG SHOT"));
+        assert_eq!(tag.artist(), Some("Toby Fox"));
+        assert_eq!(tag.genre(), Some("Soundtrack"));
+        assert_eq!(tag.year(), Some(2021));
+        let picture = tag.pictures().next().unwrap();
+        assert_eq!(picture.data, ARTWORK);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_kept_when_tagging_fails() -> Result<(), crate::Error> {
+        let server = FixtureServer::start(|request, base| {
+            if request.url().starts_with("/stream/track.mp3") {
+                Reply::ok("neither mp3 nor mp4")
+            } else {
+                soundcloud_fixtures(request, base)
+            }
+        });
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let track = client.track(TRACK_ID)?;
+
+        let downloaded = client.download(&track, dir.path(), &TranscodingPolicy::default())?;
+
+        assert!(matches!(downloaded.tag_error, Some(crate::Error::Tags(_))));
+        assert_eq!(std::fs::read(&downloaded.path)?, b"neither mp3 nor mp4");
+        assert!(!part_path(&downloaded.path).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_skips_existing() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let track = client.track(TRACK_ID)?;
+
+        let path = dir.path().join("Toby Fox - BIG SHOT.mp3");
+        std::fs::write(&path, "already here")?;
+
+        assert_eq!(
+            client
+                .download(&track, dir.path(), &TranscodingPolicy::default())?
+                .path,
+            path
+        );
+        assert_eq!(std::fs::read(&path)?, b"already here");
+
+        Ok(())
+    }
+}
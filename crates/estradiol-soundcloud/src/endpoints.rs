@@ -1,16 +1,27 @@
-use regex::{Regex, RegexBuilder};
-use serde::de::value::Error;
+use std::io::Read;
+
+use regex::Regex;
+use serde::de::DeserializeOwned;
 use ureq::Agent;
 
-use crate::models::{
-    collections::Collection,
-    media::Stream,
-    resources::{Resource, Transcoding},
+use crate::{
+    models::{
+        collections::Collection,
+        comments::Comment,
+        likes::Like,
+        media::Stream,
+        playlists::Playlist,
+        resources::{Resource, Transcoding},
+        suggestions::SearchSuggestion,
+        tracks::Track,
+        users::User,
+    },
+    search::{SearchFilters, SearchKind},
 };
 
 pub(crate) const SOUNDCLOUD: &str = "https://soundcloud.com";
-const SOUNDCLOUD_API_V2: &str = "https://api-v2.soundcloud.com";
-const SEARCH: &str = "/search";
+pub(crate) const SOUNDCLOUD_API_V2: &str = "https://api-v2.soundcloud.com";
+pub(crate) const SOUNDCLOUD_CDN: &str = "https://a-v2.sndcdn.com";
 const TRACKS: &str = "/tracks/{id}";
 const TRACKS_COMMENTS: &str = "/comments";
 const TRACKS_RELATED: &str = "/related";
@@ -18,26 +29,342 @@ const TRACKS_ALBUMS: &str = "/albums";
 const TRACKS_PLAYLISTS: &str = "/playlists";
 const TRACKS_LIKERS: &str = "/likers";
 const TRACKS_REPOSTERS: &str = "/reposters";
+const TRACKS_BY_IDS: &str = "/tracks";
+const USERS: &str = "/users/{id}";
+const USERS_TRACKS: &str = "/tracks";
+const USERS_PLAYLISTS: &str = "/playlists";
+const USERS_LIKES: &str = "/likes";
+const USERS_FOLLOWINGS: &str = "/followings";
+const PLAYLISTS: &str = "/playlists/{id}";
+const RESOLVE: &str = "/resolve";
+const SEARCH_QUERIES: &str = "/search/queries";
 
-pub(crate) fn get_track(agent: &Agent, client_id: &str, id: i64) -> Result<Resource, super::Error> {
-    let filename = TRACKS.replace("{id}", &id.to_string());
-    let path = format!("{SOUNDCLOUD_API_V2}{filename}");
+/// Most ids `/tracks?ids=` accepts per request.
+pub(crate) const TRACKS_BY_IDS_MAX: usize = 50;
 
-    let res = match agent
-        .get(path.as_str())
-        .query("client_id", client_id)
-        .call()
-    {
-        Ok(res) => res,
-        Err(err) => return Err(crate::Error::Ureq(Box::new(err))),
-    };
+/// Base URLs the endpoints are resolved against.
+#[derive(Debug, Clone)]
+pub(crate) struct BaseUrls {
+    pub(crate) web: String,
+    pub(crate) api_v2: String,
+    pub(crate) cdn: String,
+}
 
-    match res.into_json::<Resource>() {
-        Ok(track) => Ok(track),
-        Err(err) => Err(crate::Error::StdIo(err)),
+impl Default for BaseUrls {
+    fn default() -> Self {
+        Self {
+            web: String::from(SOUNDCLOUD),
+            api_v2: String::from(SOUNDCLOUD_API_V2),
+            cdn: String::from(SOUNDCLOUD_CDN),
+        }
     }
 }
 
+/// Sends `request`, turning error statuses into the matching [`crate::Error`].
+pub(crate) fn call(request: ureq::Request) -> Result<ureq::Response, crate::Error> {
+    Ok(request.call()?)
+}
+
+/// Decodes a JSON response, reporting where in the document decoding failed.
+pub(crate) fn json<T: DeserializeOwned>(res: ureq::Response) -> Result<T, crate::Error> {
+    let url = String::from(res.get_url());
+    let text = res.into_string()?;
+    decode(url, &text)
+}
+
+/// Decodes the JSON `text` fetched from `url`.
+pub(crate) fn decode<T: DeserializeOwned>(url: String, text: &str) -> Result<T, crate::Error> {
+    let de = &mut serde_json::Deserializer::from_str(text);
+    serde_path_to_error::deserialize(de).map_err(|err| crate::Error::Deserialize {
+        url,
+        path: err.path().to_string(),
+        message: err.inner().to_string(),
+    })
+}
+
+pub(crate) fn track_url(base: &BaseUrls, id: i64) -> String {
+    format!("{}{}", base.api_v2, TRACKS.replace("{id}", &id.to_string()))
+}
+
+pub(crate) fn search_url(base: &BaseUrls, kind: SearchKind) -> String {
+    format!("{}{}", base.api_v2, kind.path())
+}
+
+pub(crate) fn search_suggestions_url(base: &BaseUrls) -> String {
+    format!("{}{SEARCH_QUERIES}", base.api_v2)
+}
+
+/// URLs of the web app's scripts linked from the web origin's `page`, one of
+/// which holds the `client_id`.
+pub(crate) fn script_urls(page: &str, base: &BaseUrls) -> Result<Vec<String>, crate::Error> {
+    let re = match Regex::new(
+        r"(https?)://[\w-]+(\.[\w-]+)+([\w.,@?^=%&amp;:/~+#-]*[\w@?^=%&amp;/~+#-])?",
+    ) {
+        Ok(re) => re,
+        Err(err) => return Err(crate::Error::Regex(err)),
+    };
+    let assets = format!("{}/assets/", base.cdn);
+
+    Ok(re
+        .captures_iter(page)
+        .map(|capture| String::from(&capture[0]))
+        .filter(|url| url.contains(&assets) && url.contains(".js"))
+        .collect())
+}
+
+pub(crate) fn find_client_id(script: &str) -> Result<Option<String>, crate::Error> {
+    let re = match Regex::new(r#",client_id:"(.*?)""#) {
+        Ok(re) => re,
+        Err(err) => return Err(crate::Error::Regex(err)),
+    };
+    Ok(re
+        .captures(script)
+        .map(|captures| String::from(&captures[1])))
+}
+
+pub(crate) fn get_track(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+) -> Result<Track, super::Error> {
+    let path = track_url(base, id);
+
+    let res = call(agent.get(path.as_str()).query("client_id", client_id))?;
+
+    json::<Track>(res)
+}
+
+fn get_resource<T: DeserializeOwned>(
+    agent: &Agent,
+    client_id: &str,
+    path: &str,
+) -> Result<T, super::Error> {
+    let res = call(agent.get(path).query("client_id", client_id))?;
+
+    json::<T>(res)
+}
+
+fn get_collection<T: DeserializeOwned>(
+    agent: &Agent,
+    client_id: &str,
+    path: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<T>, super::Error> {
+    let res = call(
+        agent
+            .get(path)
+            .query("client_id", client_id)
+            .query("limit", &limit.to_string())
+            .query("offset", &offset.to_string()),
+    )?;
+
+    json::<Collection<T>>(res)
+}
+
+fn get_track_collection<T: DeserializeOwned>(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    subpath: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<T>, super::Error> {
+    let filename = TRACKS.replace("{id}", &id.to_string());
+    let path = format!("{}{filename}{subpath}", base.api_v2);
+    get_collection(agent, client_id, &path, limit, offset)
+}
+
+pub(crate) fn get_track_comments(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<Comment>, super::Error> {
+    get_track_collection(agent, base, client_id, id, TRACKS_COMMENTS, limit, offset)
+}
+
+pub(crate) fn get_track_related(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<Track>, super::Error> {
+    get_track_collection(agent, base, client_id, id, TRACKS_RELATED, limit, offset)
+}
+
+pub(crate) fn get_track_albums(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<Playlist>, super::Error> {
+    get_track_collection(agent, base, client_id, id, TRACKS_ALBUMS, limit, offset)
+}
+
+pub(crate) fn get_track_playlists(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<Playlist>, super::Error> {
+    get_track_collection(agent, base, client_id, id, TRACKS_PLAYLISTS, limit, offset)
+}
+
+pub(crate) fn get_track_likers(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<User>, super::Error> {
+    get_track_collection(agent, base, client_id, id, TRACKS_LIKERS, limit, offset)
+}
+
+pub(crate) fn get_track_reposters(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<User>, super::Error> {
+    get_track_collection(agent, base, client_id, id, TRACKS_REPOSTERS, limit, offset)
+}
+
+/// Fetches full tracks for at most [`TRACKS_BY_IDS_MAX`] ids, in no particular order.
+pub(crate) fn get_tracks(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    ids: &[i64],
+) -> Result<Vec<Track>, super::Error> {
+    let path = format!("{}{TRACKS_BY_IDS}", base.api_v2);
+    let ids = ids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let res = call(
+        agent
+            .get(&path)
+            .query("client_id", client_id)
+            .query("ids", &ids),
+    )?;
+
+    json::<Vec<Track>>(res)
+}
+
+pub(crate) fn get_user(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+) -> Result<User, super::Error> {
+    let filename = USERS.replace("{id}", &id.to_string());
+    let path = format!("{}{filename}", base.api_v2);
+    get_resource(agent, client_id, &path)
+}
+
+fn get_user_collection<T: DeserializeOwned>(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    subpath: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<T>, super::Error> {
+    let filename = USERS.replace("{id}", &id.to_string());
+    let path = format!("{}{filename}{subpath}", base.api_v2);
+    get_collection(agent, client_id, &path, limit, offset)
+}
+
+pub(crate) fn get_user_tracks(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<Track>, super::Error> {
+    get_user_collection(agent, base, client_id, id, USERS_TRACKS, limit, offset)
+}
+
+pub(crate) fn get_user_playlists(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<Playlist>, super::Error> {
+    get_user_collection(agent, base, client_id, id, USERS_PLAYLISTS, limit, offset)
+}
+
+pub(crate) fn get_user_likes(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<Like>, super::Error> {
+    get_user_collection(agent, base, client_id, id, USERS_LIKES, limit, offset)
+}
+
+pub(crate) fn get_user_followings(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Collection<User>, super::Error> {
+    get_user_collection(agent, base, client_id, id, USERS_FOLLOWINGS, limit, offset)
+}
+
+pub(crate) fn get_playlist(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    id: i64,
+) -> Result<Playlist, super::Error> {
+    let filename = PLAYLISTS.replace("{id}", &id.to_string());
+    let path = format!("{}{filename}", base.api_v2);
+    get_resource(agent, client_id, &path)
+}
+
+pub(crate) fn get_resolve(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    url: &str,
+) -> Result<Resource, super::Error> {
+    let path = format!("{}{RESOLVE}", base.api_v2);
+
+    let res = call(
+        agent
+            .get(&path)
+            .query("client_id", client_id)
+            .query("url", url),
+    )?;
+
+    json::<Resource>(res)
+}
+
 pub(crate) fn get_stream(
     agent: &Agent,
     client_id: &str,
@@ -45,120 +372,118 @@ pub(crate) fn get_stream(
 ) -> Result<Stream, super::Error> {
     let path = transcoding.url();
 
-    let res = match agent.get(&path).query("client_id", client_id).call() {
-        Ok(res) => res,
-        Err(err) => return Err(crate::Error::Ureq(Box::new(err))),
-    };
-
-    match res.into_json::<Stream>() {
-        Ok(stream) => Ok(stream),
-        Err(err) => Err(crate::Error::StdIo(err)),
-    }
-}
+    let res = call(agent.get(&path).query("client_id", client_id))?;
 
-pub(crate) fn get_stream_bytes(agent: &Agent, stream: &Stream) -> Result<Vec<u8>, super::Error> {
-    let path = stream.url();
-    get_bytes(agent, path.as_str())
+    json::<Stream>(res)
 }
 
 pub(crate) fn get_bytes(agent: &Agent, url: &str) -> Result<Vec<u8>, super::Error> {
     let path = url;
 
-    let res = match agent.get(&path).call() {
-        Ok(res) => res,
-        Err(err) => return Err(crate::Error::Ureq(Box::new(err))),
-    };
+    let res = call(agent.get(path))?;
 
     let mut bytes: Vec<u8> = Vec::new();
-    if res.into_reader().read_to_end(&mut bytes).is_ok() {
-        return Ok(bytes);
-    }
+    res.into_reader().read_to_end(&mut bytes)?;
 
-    Err(crate::Error::InvalidData(String::from(path)))
+    Ok(bytes)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn get_search(
     agent: &Agent,
+    base: &BaseUrls,
     client_id: &str,
     query: &str,
+    kind: SearchKind,
+    filters: &SearchFilters,
     limit: i64,
     offset: i64,
 ) -> Result<Collection, super::Error> {
-    let path = format!("{SOUNDCLOUD_API_V2}{SEARCH}");
+    let path = search_url(base, kind);
 
-    let res = match agent
+    let mut request = agent
         .get(&path)
         .query("client_id", client_id)
         .query("q", query)
         .query("limit", &limit.to_string())
-        .query("offset", &offset.to_string())
-        .call()
-    {
-        Ok(res) => res,
-        Err(err) => return Err(crate::Error::Ureq(Box::new(err))),
-    };
-
-    match res.into_json::<Collection>() {
-        Ok(search_results) => Ok(search_results),
-        Err(err) => Err(crate::Error::StdIo(err)),
+        .query("offset", &offset.to_string());
+    for (name, value) in filters.query_pairs() {
+        request = request.query(name, &value);
     }
+    let res = call(request)?;
+
+    json::<Collection>(res)
 }
 
-pub(crate) fn get_client_id(agent: &Agent) -> Result<String, super::Error> {
-    let res = match agent.get(SOUNDCLOUD).call() {
-        Ok(res) => res,
-        Err(err) => return Err(crate::Error::Ureq(Box::new(err))),
-    };
+pub(crate) fn get_search_suggestions(
+    agent: &Agent,
+    base: &BaseUrls,
+    client_id: &str,
+    query: &str,
+    limit: i64,
+) -> Result<Collection<SearchSuggestion>, super::Error> {
+    let path = search_suggestions_url(base);
 
-    let body = match res.into_string() {
-        Ok(body) => body,
-        Err(err) => return Err(crate::Error::StdIo(err)),
-    };
+    let res = call(
+        agent
+            .get(&path)
+            .query("client_id", client_id)
+            .query("q", query)
+            .query("limit", &limit.to_string()),
+    )?;
 
-    let re = match Regex::new(
-        r"(https)://[\w-]+(\.[\w-]+)+([\w.,@?^=%&amp;:/~+#-]*[\w@?^=%&amp;/~+#-])?",
-    ) {
-        Ok(re) => re,
-        Err(err) => return Err(crate::Error::Regex(err)),
-    };
+    json::<Collection<SearchSuggestion>>(res)
+}
 
-    for capture in re.captures_iter(&body) {
-        let url = &capture[0];
-        if url.contains("https://a-v2.sndcdn.com/assets/") && url.contains(".js") {
-            let Ok(res) = agent.get(url).call() else {
-                continue;
-            };
-            let Ok(body) = res.into_string() else {
-                continue;
-            };
-            let Ok(re) = Regex::new(r#",client_id:"(.*?)""#) else {
-                continue;
-            };
-            let Some(captures) = re.captures(&body) else {
-                continue;
-            };
-            return Ok(String::from(&captures[1]));
+/// Fetches the page behind a collection's `next_href`, which omits the `client_id`.
+pub(crate) fn get_next_page<T: DeserializeOwned>(
+    agent: &Agent,
+    client_id: &str,
+    next_href: &str,
+) -> Result<Collection<T>, super::Error> {
+    let res = call(agent.get(next_href).query("client_id", client_id))?;
+
+    json::<Collection<T>>(res)
+}
+
+pub(crate) fn get_client_id(agent: &Agent, base: &BaseUrls) -> Result<String, super::Error> {
+    let res = call(agent.get(&base.web))?;
+
+    let page = res.into_string()?;
+
+    for url in script_urls(&page, base)? {
+        let Ok(res) = agent.get(&url).call() else {
+            continue;
+        };
+        let Ok(script) = res.into_string() else {
+            continue;
+        };
+        if let Some(client_id) = find_client_id(&script)? {
+            return Ok(client_id);
         }
     }
 
-    Err(crate::Error::Unknown)
+    Err(crate::Error::ClientIdScrape {
+        url: base.web.clone(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use core::panic;
 
-    use crate::client::AGENT;
+    use crate::ClientBuilder;
 
-    use super::get_client_id;
+    use super::{get_client_id, BaseUrls};
 
     #[test]
+    #[ignore = "hits the live SoundCloud site"]
     fn test_client_id() {
-        let agent = &AGENT.clone();
+        let agent = &ClientBuilder::new().agent();
 
-        match get_client_id(agent) {
+        match get_client_id(agent, &BaseUrls::default()) {
             Ok(client_id) => println!("{client_id:?}"),
             Err(err) => panic!("{err:?}"),
-        };
+        }
     }
 }
@@ -1,17 +1,250 @@
+use std::{io::ErrorKind, time::Duration};
+
 use thiserror::Error;
 
-use crate::models::media::Stream;
+/// Most bytes of an error response body kept in [`Error::Status`].
+const BODY_SNIPPET_LEN: usize = 256;
 
 #[derive(Error, Debug)]
 pub enum Error {
-    #[error("ureq error")]
-    Ureq(#[from] Box<ureq::Error>),
+    /// The request never got a response: DNS, connection, TLS or timeout.
+    #[error("transport error: {0}")]
+    Transport(Box<ureq::Transport>),
+    /// The request never got a response, from the [`AsyncClient`](crate::AsyncClient).
+    #[cfg(feature = "async")]
+    #[error("transport error: {0}")]
+    AsyncTransport(Box<reqwest::Error>),
+    /// A response with a status not covered by a more specific variant.
+    #[error("HTTP {status} from {url}: {body}")]
+    Status {
+        status: u16,
+        url: String,
+        body: String,
+    },
+    #[error("rate limited by {url}")]
+    RateLimited {
+        url: String,
+        /// How long the server asked us to wait, from `Retry-After`.
+        retry_after: Option<Duration>,
+    },
+    #[error("not found: {url}")]
+    NotFound { url: String },
+    /// The `client_id` was rejected, usually because it has expired.
+    #[error("HTTP {status} from {url}, the client_id may have expired")]
+    Unauthorized { status: u16, url: String },
+    /// Access was refused. From api-v2 this is also how a stale `client_id`
+    /// is rejected; otherwise the resource is private or geo-blocked.
+    #[error("forbidden: {url}")]
+    Forbidden { url: String },
+    #[error("failed to decode response from {url} at `{path}`: {message}")]
+    Deserialize {
+        url: String,
+        /// Where in the document decoding failed, like `collection[3].user.id`.
+        path: String,
+        message: String,
+    },
+    /// The track cannot be played here, for example because it is geo-blocked.
+    #[error("track {id} is not streamable: {reason}")]
+    Unstreamable { id: i64, reason: String },
+    #[error("could not find a client_id in {url}")]
+    ClientIdScrape { url: String },
     #[error("std::io error")]
     StdIo(#[from] std::io::Error),
     #[error("failed to get bytes {0}")]
     InvalidData(String),
+    #[error("invalid HLS playlist: {0}")]
+    Hls(String),
     #[error("regex error")]
     Regex(regex::Error),
-    #[error("unknown error")]
-    Unknown,
+    /// Metadata could not be written into an audio file.
+    #[error("failed to write tags: {0}")]
+    Tags(String),
+}
+
+impl Error {
+    /// Whether the same request may succeed if sent again later.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Transport(_) | Error::RateLimited { .. } => true,
+            // Only the connection failing; a full disk stays full however
+            // often the write is retried.
+            Error::StdIo(err) => matches!(
+                err.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::NotConnected
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::TimedOut
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::Interrupted
+            ),
+            #[cfg(feature = "async")]
+            Error::AsyncTransport(_) => true,
+            Error::Status { status, .. } => *status >= 500 || *status == 408,
+            Error::NotFound { .. }
+            | Error::Unauthorized { .. }
+            | Error::Forbidden { .. }
+            | Error::Deserialize { .. }
+            | Error::Unstreamable { .. }
+            | Error::ClientIdScrape { .. }
+            | Error::InvalidData(_)
+            | Error::Hls(_)
+            | Error::Regex(_)
+            | Error::Tags(_) => false,
+        }
+    }
+
+    /// HTTP status of the response that caused this error, if there was one.
+    #[must_use]
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Status { status, .. } | Error::Unauthorized { status, .. } => Some(*status),
+            Error::RateLimited { .. } => Some(429),
+            Error::Forbidden { .. } => Some(403),
+            Error::NotFound { .. } => Some(404),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// Whether the server may have rejected the `client_id` rather than the
+    /// request: any 401, or a 403 from the api-v2 host at `api_v2`.
+    pub(crate) fn rejects_client_id(&self, api_v2: &str) -> bool {
+        match self {
+            Error::Unauthorized { .. } => true,
+            Error::Forbidden { url } => url.starts_with(api_v2),
+            _ => false,
+        }
+    }
+
+    /// Classifies an error response by its status.
+    pub(crate) fn from_status(
+        status: u16,
+        url: String,
+        retry_after: Option<&str>,
+        body: impl FnOnce() -> String,
+    ) -> Self {
+        match status {
+            401 => Error::Unauthorized { status, url },
+            403 => Error::Forbidden { url },
+            404 => Error::NotFound { url },
+            429 => Error::RateLimited {
+                retry_after: retry_after
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs),
+                url,
+            },
+            _ => {
+                let mut body = body();
+                if body.len() > BODY_SNIPPET_LEN {
+                    let end = (0..=BODY_SNIPPET_LEN)
+                        .rev()
+                        .find(|&i| body.is_char_boundary(i))
+                        .unwrap_or_default();
+                    body.truncate(end);
+                }
+                Error::Status { status, url, body }
+            }
+        }
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(err: ureq::Error) -> Self {
+        match err {
+            ureq::Error::Transport(transport) => Error::Transport(Box::new(transport)),
+            ureq::Error::Status(status, res) => {
+                let url = String::from(res.get_url());
+                let retry_after = res.header("Retry-After").map(String::from);
+                Error::from_status(status, url, retry_after.as_deref(), || {
+                    res.into_string().unwrap_or_default()
+                })
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::AsyncTransport(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::ErrorKind, time::Duration};
+
+    use crate::testing::{FixtureServer, Reply};
+
+    use super::Error;
+
+    fn get(reply: fn() -> Reply) -> Error {
+        let server = FixtureServer::start(move |_, _| reply());
+        let err = ureq::get(&format!("{}/x", server.url()))
+            .call()
+            .unwrap_err();
+        Error::from(err)
+    }
+
+    #[test]
+    fn test_classifies_statuses() {
+        assert!(matches!(get(|| Reply::status(404)), Error::NotFound { .. }));
+        assert!(matches!(
+            get(|| Reply::status(401)),
+            Error::Unauthorized { status: 401, .. }
+        ));
+        assert!(matches!(
+            get(|| Reply::status(403)),
+            Error::Forbidden { .. }
+        ));
+        assert!(matches!(
+            get(|| Reply::status(429).header("Retry-After", "7")),
+            Error::RateLimited {
+                retry_after: Some(retry_after),
+                ..
+            } if retry_after == Duration::from_secs(7)
+        ));
+
+        let err = get(|| Reply {
+            status: 503,
+            headers: Vec::new(),
+            body: "x".repeat(1000).into_bytes(),
+        });
+        assert!(err.is_retryable());
+        let Error::Status { status, body, .. } = err else {
+            panic!("expected a status error");
+        };
+        assert_eq!(status, 503);
+        assert_eq!(body.len(), 256);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(get(|| Reply::status(429)).is_retryable());
+        assert!(!get(|| Reply::status(404)).is_retryable());
+        assert!(!get(|| Reply::status(403)).is_retryable());
+        assert!(!get(|| Reply::status(400)).is_retryable());
+        assert!(Error::from(std::io::Error::from(ErrorKind::UnexpectedEof)).is_retryable());
+        assert!(Error::from(std::io::Error::from(ErrorKind::ConnectionReset)).is_retryable());
+        assert!(!Error::from(std::io::Error::from(ErrorKind::StorageFull)).is_retryable());
+        assert!(!Error::from(std::io::Error::from(ErrorKind::PermissionDenied)).is_retryable());
+    }
+
+    #[test]
+    fn test_rejects_client_id() {
+        let forbidden = |url: &str| Error::Forbidden {
+            url: String::from(url),
+        };
+
+        assert!(get(|| Reply::status(401)).rejects_client_id("https://api-v2.soundcloud.com"));
+        assert!(forbidden("https://api-v2.soundcloud.com/tracks/1")
+            .rejects_client_id("https://api-v2.soundcloud.com"));
+        assert!(!forbidden("https://cf-media.sndcdn.com/track.mp3")
+            .rejects_client_id("https://api-v2.soundcloud.com"));
+        assert!(!get(|| Reply::status(404)).rejects_client_id("https://api-v2.soundcloud.com"));
+    }
 }
@@ -0,0 +1,273 @@
+//! Parsing and fetching of HLS media playlists, for tracks that offer no
+//! progressive transcoding.
+
+use std::{
+    collections::VecDeque,
+    io::{Cursor, Read},
+    time::Duration,
+};
+
+use crate::Client;
+
+const EXTM3U: &str = "#EXTM3U";
+const EXTINF: &str = "#EXTINF:";
+const EXT_X_MAP: &str = "#EXT-X-MAP:";
+const EXT_X_KEY: &str = "#EXT-X-KEY:";
+const EXT_X_STREAM_INF: &str = "#EXT-X-STREAM-INF:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    uri: String,
+    duration: Duration,
+}
+
+impl Segment {
+    #[must_use]
+    pub fn uri(&self) -> String {
+        self.uri.clone()
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// An HLS media playlist with its segment URIs resolved to absolute URLs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaPlaylist {
+    map: Option<String>,
+    segments: Vec<Segment>,
+}
+
+impl MediaPlaylist {
+    /// Parses an m3u8 media playlist fetched from `url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a media playlist or uses
+    /// encryption, which is not supported.
+    pub fn parse(text: &str, url: &str) -> Result<Self, crate::Error> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(EXTM3U) {
+            return Err(crate::Error::Hls(String::from("missing #EXTM3U header")));
+        }
+
+        let mut map = None;
+        let mut segments = Vec::new();
+        let mut duration = None;
+
+        for line in lines {
+            if let Some(attributes) = line.strip_prefix(EXT_X_MAP) {
+                let Some(uri) = attribute(attributes, "URI") else {
+                    return Err(crate::Error::Hls(String::from("#EXT-X-MAP without URI")));
+                };
+                map = Some(resolve(url, &uri));
+            } else if let Some(attributes) = line.strip_prefix(EXT_X_KEY) {
+                if attribute(attributes, "METHOD").as_deref() != Some("NONE") {
+                    return Err(crate::Error::Hls(String::from(
+                        "encrypted segments are not supported",
+                    )));
+                }
+            } else if line.starts_with(EXT_X_STREAM_INF) {
+                return Err(crate::Error::Hls(String::from(
+                    "expected a media playlist, got a master playlist",
+                )));
+            } else if let Some(info) = line.strip_prefix(EXTINF) {
+                let seconds = info.split(',').next().unwrap_or_default();
+                let Some(seconds) = seconds
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                else {
+                    return Err(crate::Error::Hls(format!("invalid #EXTINF {info}")));
+                };
+                duration = Some(seconds);
+            } else if !line.starts_with('#') {
+                segments.push(Segment {
+                    uri: resolve(url, line),
+                    duration: duration.take().unwrap_or_default(),
+                });
+            }
+        }
+
+        Ok(Self { map, segments })
+    }
+
+    /// Initialization section to prepend to the segments, for fMP4 playlists.
+    #[must_use]
+    pub fn map(&self) -> Option<String> {
+        self.map.clone()
+    }
+
+    #[must_use]
+    pub fn segments(&self) -> Vec<Segment> {
+        self.segments.clone()
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+
+    /// URLs to fetch, in order, to assemble the whole stream.
+    #[must_use]
+    pub fn urls(&self) -> Vec<String> {
+        self.map
+            .iter()
+            .cloned()
+            .chain(self.segments.iter().map(Segment::uri))
+            .collect()
+    }
+}
+
+/// Reads an HLS stream as one continuous byte stream, fetching each segment
+/// only once the previous one has been read.
+#[derive(Debug)]
+pub struct HlsReader<'a> {
+    client: &'a Client,
+    urls: VecDeque<String>,
+    current: Cursor<Vec<u8>>,
+}
+
+impl<'a> HlsReader<'a> {
+    pub(crate) fn new(client: &'a Client, playlist: &MediaPlaylist) -> Self {
+        Self {
+            client,
+            urls: playlist.urls().into(),
+            current: Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Read for HlsReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            let Some(url) = self.urls.pop_front() else {
+                return Ok(0);
+            };
+            let bytes = self.client.bytes(&url).map_err(std::io::Error::other)?;
+            self.current = Cursor::new(bytes);
+        }
+    }
+}
+
+/// Reads a quoted or bare attribute from an attribute list.
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        let (value, next) = if let Some(quoted) = value.strip_prefix('"') {
+            let (value, next) = quoted.split_once('"')?;
+            (value, next.trim_start_matches(','))
+        } else {
+            value.split_once(',').unwrap_or((value, ""))
+        };
+        if key.trim() == name {
+            return Some(String::from(value));
+        }
+        rest = next;
+    }
+    None
+}
+
+/// Resolves a playlist entry against the playlist's own URL.
+fn resolve(base: &str, uri: &str) -> String {
+    if uri.starts_with("http://") || uri.starts_with("https://") {
+        return String::from(uri);
+    }
+    let base = base.split(['?', '#']).next().unwrap_or(base);
+    if uri.starts_with('/') {
+        let scheme_end = base.find("://").map_or(0, |i| i + 3);
+        let origin_end = base[scheme_end..]
+            .find('/')
+            .map_or(base.len(), |i| scheme_end + i);
+        return format!("{}{uri}", &base[..origin_end]);
+    }
+    let directory_end = base.rfind('/').map_or(base.len(), |i| i + 1);
+    format!("{}{uri}", &base[..directory_end])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{resolve, MediaPlaylist};
+
+    const URL: &str = "https://cf-hls-media.sndcdn.com/playlist/abc/playlist.m3u8?Policy=x";
+
+    #[test]
+    fn test_parse_mp3_playlist() -> Result<(), crate::Error> {
+        let playlist = MediaPlaylist::parse(include_str!("../fixtures/hls_mp3.m3u8"), URL)?;
+        let segments = playlist.segments();
+
+        assert!(playlist.map().is_none());
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].duration(), Duration::from_secs_f64(1.985_272));
+        assert_eq!(
+            segments[0].uri(),
+            "https://cf-hls-media.sndcdn.com/playlist/abc/segment0.mp3"
+        );
+        assert_eq!(
+            segments[2].uri(),
+            "https://cf-hls-media.sndcdn.com/hls/segment2.mp3"
+        );
+        assert_eq!(playlist.urls().len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_aac_playlist_with_map() -> Result<(), crate::Error> {
+        let playlist = MediaPlaylist::parse(include_str!("../fixtures/hls_aac.m3u8"), URL)?;
+
+        assert_eq!(
+            playlist.map().unwrap(),
+            "https://cf-hls-media.sndcdn.com/playlist/abc/init.mp4"
+        );
+        assert_eq!(playlist.segments().len(), 2);
+        assert_eq!(playlist.urls()[0], playlist.map().unwrap());
+        assert_eq!(playlist.duration(), Duration::from_secs(12));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_encrypted_and_master_playlists() {
+        let encrypted = "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"key\"\n#EXTINF:10,\na.ts\n";
+        let master = "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=128000\nlow.m3u8\n";
+
+        assert!(MediaPlaylist::parse(encrypted, URL).is_err());
+        assert!(MediaPlaylist::parse(master, URL).is_err());
+        assert!(MediaPlaylist::parse("not a playlist", URL).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_durations() {
+        for info in ["-1", "NaN", "inf"] {
+            let playlist = format!("#EXTM3U\n#EXTINF:{info},\na.mp3\n");
+
+            assert!(matches!(
+                MediaPlaylist::parse(&playlist, URL),
+                Err(crate::Error::Hls(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve(URL, "https://a/b.mp3"), "https://a/b.mp3");
+        assert_eq!(
+            resolve(URL, "/x/y.mp3"),
+            "https://cf-hls-media.sndcdn.com/x/y.mp3"
+        );
+        assert_eq!(
+            resolve(URL, "y.mp3"),
+            "https://cf-hls-media.sndcdn.com/playlist/abc/y.mp3"
+        );
+    }
+}
@@ -2,9 +2,25 @@
 #![warn(clippy::perf)]
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "async")]
+pub use async_client::AsyncClient;
+pub mod cache;
 mod client;
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
 mod error;
 pub use error::Error;
+pub mod download;
 pub mod endpoints;
+pub mod hls;
 pub mod models;
+mod paginator;
+pub use paginator::Paginator;
+mod retry;
+pub use retry::{RateLimit, RetryPolicy};
+pub mod search;
+pub mod streaming;
+pub mod tags;
+#[cfg(test)]
+mod testing;
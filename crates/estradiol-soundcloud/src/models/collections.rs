@@ -2,27 +2,59 @@ use serde::Deserialize;
 
 use super::resources::Resource;
 
+/// A page of results from a collection endpoint, of resources unless noted.
 #[derive(Debug, Deserialize)]
-pub struct Collection {
-    collection: Vec<Resource>,
+#[allow(clippy::struct_field_names)]
+pub struct Collection<T = Resource> {
+    collection: Vec<T>,
     total_results: Option<i64>,
     next_href: Option<String>,
     query_urn: Option<String>,
 }
 
-impl Collection {
-    pub fn collection(&self) -> Vec<Resource> {
+impl<T: Clone> Collection<T> {
+    #[must_use]
+    pub fn collection(&self) -> Vec<T> {
         self.collection.clone()
     }
+}
+
+impl<T> Collection<T> {
+    #[must_use]
+    pub fn into_collection(self) -> Vec<T> {
+        self.collection
+    }
+
+    /// Items loaded so far, across every page appended.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.collection.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.collection.is_empty()
+    }
+
+    /// Adds the items of `page`, the page following this one, and takes its
+    /// link to the page after.
+    pub fn append(&mut self, page: Collection<T>) {
+        self.collection.extend(page.collection);
+        self.next_href = page.next_href;
+        self.total_results = page.total_results.or(self.total_results);
+    }
 
+    #[must_use]
     pub fn total_results(&self) -> Option<i64> {
-        self.total_results.clone()
+        self.total_results
     }
 
+    #[must_use]
     pub fn next_href(&self) -> Option<String> {
         self.next_href.clone()
     }
 
+    #[must_use]
     pub fn query_urn(&self) -> Option<String> {
         self.query_urn.clone()
     }
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use super::{de, users::User};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Comment {
+    id: i64,
+    body: String,
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "de::duration_ms")]
+    timestamp: Option<Duration>,
+    track_id: Option<i64>,
+    user: Option<User>,
+}
+
+impl Comment {
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[must_use]
+    pub fn body(&self) -> String {
+        self.body.clone()
+    }
+
+    #[must_use]
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+
+    /// Position in the track the comment is pinned to.
+    #[must_use]
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    #[must_use]
+    pub fn track_id(&self) -> Option<i64> {
+        self.track_id
+    }
+
+    /// The comment's author.
+    #[must_use]
+    pub fn user(&self) -> Option<User> {
+        self.user.clone()
+    }
+}
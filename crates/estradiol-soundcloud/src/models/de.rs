@@ -0,0 +1,87 @@
+//! Deserializers for the API's loosely typed fields.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+/// Reads an optional millisecond count as a [`Duration`].
+pub(crate) fn duration_ms<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let millis = Option::<u64>::deserialize(deserializer)?;
+    Ok(millis.map(Duration::from_millis))
+}
+
+/// Reads an optional space-delimited tag list into its tags.
+pub(crate) fn tag_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tag_list = Option::<String>::deserialize(deserializer)?;
+    Ok(tag_list.as_deref().map(parse_tag_list).unwrap_or_default())
+}
+
+/// Splits a tag list on spaces, keeping double-quoted multi-word tags whole.
+///
+/// `deltarune "toby fox" spamton` yields `deltarune`, `toby fox` and `spamton`.
+#[must_use]
+pub fn parse_tag_list(tag_list: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut tag = String::new();
+    let mut quoted = false;
+
+    for c in tag_list.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !tag.is_empty() {
+                    tags.push(std::mem::take(&mut tag));
+                }
+            }
+            c => tag.push(c),
+        }
+    }
+    if !tag.is_empty() {
+        tags.push(tag);
+    }
+
+    tags
+}
+
+/// Joins tags back into a tag list, quoting multi-word tags.
+#[must_use]
+pub fn format_tag_list(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| {
+            if tag.contains(char::is_whitespace) {
+                format!("\"{tag}\"")
+            } else {
+                tag.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_tag_list, parse_tag_list};
+
+    #[test]
+    fn test_parse_tag_list() {
+        assert_eq!(
+            parse_tag_list(r#"deltarune "toby fox"  spamton"#),
+            ["deltarune", "toby fox", "spamton"]
+        );
+        assert_eq!(parse_tag_list(r#""big shot""#), ["big shot"]);
+        assert!(parse_tag_list("   ").is_empty());
+    }
+
+    #[test]
+    fn test_format_tag_list_round_trips() {
+        let tags = parse_tag_list(r#"deltarune "toby fox" spamton"#);
+
+        assert_eq!(format_tag_list(&tags), r#"deltarune "toby fox" spamton"#);
+    }
+}
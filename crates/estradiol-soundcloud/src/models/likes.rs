@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use super::{playlists::Playlist, resources::Resource, tracks::Track};
+
+/// An entry of a user's likes, holding either a track or a playlist.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Like {
+    created_at: Option<DateTime<Utc>>,
+    track: Option<Track>,
+    playlist: Option<Playlist>,
+}
+
+impl Like {
+    #[must_use]
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+
+    #[must_use]
+    pub fn track(&self) -> Option<Track> {
+        self.track.clone()
+    }
+
+    #[must_use]
+    pub fn playlist(&self) -> Option<Playlist> {
+        self.playlist.clone()
+    }
+
+    /// The liked track or playlist.
+    #[must_use]
+    pub fn resource(&self) -> Option<Resource> {
+        self.track()
+            .map(Resource::Track)
+            .or_else(|| self.playlist().map(Resource::Playlist))
+    }
+}
@@ -6,6 +6,7 @@ pub struct Stream {
 }
 
 impl Stream {
+    #[must_use]
     pub fn url(&self) -> String {
         self.url.clone()
     }
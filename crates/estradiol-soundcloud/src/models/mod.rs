@@ -1,3 +1,10 @@
 pub mod collections;
+pub mod comments;
+pub(crate) mod de;
+pub mod likes;
 pub mod media;
+pub mod playlists;
 pub mod resources;
+pub mod suggestions;
+pub mod tracks;
+pub mod users;
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use super::{de, tracks::Track, users::User};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Playlist {
+    id: i64,
+    title: Option<String>,
+    artwork_url: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "de::duration_ms")]
+    duration: Option<Duration>,
+    genre: Option<String>,
+    is_album: Option<bool>,
+    likes_count: Option<i64>,
+    permalink_url: Option<String>,
+    #[serde(default, deserialize_with = "de::tag_list")]
+    tag_list: Vec<String>,
+    track_count: Option<i64>,
+    tracks: Option<Vec<Track>>,
+    user: Option<User>,
+}
+
+impl Playlist {
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[must_use]
+    pub fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
+
+    #[must_use]
+    pub fn artwork_url(&self) -> Option<String> {
+        self.artwork_url.clone()
+    }
+
+    #[must_use]
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    #[must_use]
+    pub fn genre(&self) -> Option<String> {
+        self.genre.clone()
+    }
+
+    #[must_use]
+    pub fn is_album(&self) -> bool {
+        self.is_album.unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn likes_count(&self) -> Option<i64> {
+        self.likes_count
+    }
+
+    #[must_use]
+    pub fn permalink_url(&self) -> Option<String> {
+        self.permalink_url.clone()
+    }
+
+    #[must_use]
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tag_list.clone()
+    }
+
+    #[must_use]
+    pub fn track_count(&self) -> Option<i64> {
+        self.track_count
+    }
+
+    #[must_use]
+    pub fn tracks(&self) -> Option<Vec<Track>> {
+        self.tracks.clone()
+    }
+
+    pub(crate) fn set_tracks(&mut self, tracks: Vec<Track>) {
+        self.tracks = Some(tracks);
+    }
+
+    #[must_use]
+    pub fn user(&self) -> Option<User> {
+        self.user.clone()
+    }
+}
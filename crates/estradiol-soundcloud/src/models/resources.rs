@@ -1,103 +1,201 @@
+use chrono::SecondsFormat;
 use serde::Deserialize;
 
+use super::{de::format_tag_list, playlists::Playlist, tracks::Track, users::User};
+
+/// Any track, user or playlist, tagged by its `kind`.
 #[derive(Debug, Deserialize, Clone)]
-pub struct Resource {
-    artwork_url: Option<String>,
-    avatar_url: Option<String>,
-    created_at: Option<String>,
-    duration: Option<i64>,
-    followers_count: Option<i64>,
-    followings_count: Option<i64>,
-    full_duration: Option<i64>,
-    genre: Option<String>,
-    id: i64,
-    kind: ResourceKind,
-    likes_count: Option<i64>,
-    permalink_url: Option<String>,
-    playback_count: Option<i64>,
-    tag_list: Option<String>,
-    title: Option<String>,
-    tracks: Option<Vec<Resource>>,
-    media: Option<Media>,
-    user: Option<Box<Resource>>,
-    username: Option<String>,
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Resource {
+    Track(Track),
+    User(User),
+    Playlist(Playlist),
 }
 
 impl Resource {
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        match self {
+            Self::Track(track) => track.id(),
+            Self::User(user) => user.id(),
+            Self::Playlist(playlist) => playlist.id(),
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> ResourceKind {
+        match self {
+            Self::Track(_) => ResourceKind::Track,
+            Self::User(_) => ResourceKind::User,
+            Self::Playlist(_) => ResourceKind::Playlist,
+        }
+    }
+
+    #[must_use]
+    pub fn as_track(&self) -> Option<&Track> {
+        match self {
+            Self::Track(track) => Some(track),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_user(&self) -> Option<&User> {
+        match self {
+            Self::User(user) => Some(user),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_playlist(&self) -> Option<&Playlist> {
+        match self {
+            Self::Playlist(playlist) => Some(playlist),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a stub track, carrying little more than its id.
+    #[must_use]
+    pub fn is_stub(&self) -> bool {
+        self.as_track().is_some_and(Track::is_stub)
+    }
+}
+
+/// Accessors from when `Resource` was a single struct of optional fields,
+/// returning `None` for fields the variant does not have.
+impl Resource {
+    #[must_use]
     pub fn artwork_url(&self) -> Option<String> {
-        self.artwork_url.clone()
+        match self {
+            Self::Track(track) => track.artwork_url(),
+            Self::User(_) => None,
+            Self::Playlist(playlist) => playlist.artwork_url(),
+        }
     }
 
+    #[must_use]
     pub fn avatar_url(&self) -> Option<String> {
-        self.avatar_url.clone()
+        self.as_user().and_then(User::avatar_url)
     }
 
+    /// Creation time as an RFC 3339 string.
+    #[must_use]
     pub fn created_at(&self) -> Option<String> {
-        self.created_at.clone()
+        let created_at = match self {
+            Self::Track(track) => track.created_at(),
+            Self::User(user) => user.created_at(),
+            Self::Playlist(playlist) => playlist.created_at(),
+        };
+        created_at.map(|created_at| created_at.to_rfc3339_opts(SecondsFormat::Secs, true))
     }
 
+    /// Duration in milliseconds.
+    #[must_use]
     pub fn duration(&self) -> Option<i64> {
-        self.duration.clone()
+        let duration = match self {
+            Self::Track(track) => track.duration(),
+            Self::User(_) => None,
+            Self::Playlist(playlist) => playlist.duration(),
+        };
+        duration.and_then(|duration| i64::try_from(duration.as_millis()).ok())
     }
 
+    #[must_use]
     pub fn followers_count(&self) -> Option<i64> {
-        self.followers_count.clone()
+        self.as_user().and_then(User::followers_count)
     }
 
+    #[must_use]
     pub fn followings_count(&self) -> Option<i64> {
-        self.followings_count.clone()
+        self.as_user().and_then(User::followings_count)
     }
 
+    /// Full duration in milliseconds.
+    #[must_use]
     pub fn full_duration(&self) -> Option<i64> {
-        self.full_duration.clone()
+        self.as_track()
+            .and_then(Track::full_duration)
+            .and_then(|duration| i64::try_from(duration.as_millis()).ok())
     }
 
+    #[must_use]
     pub fn genre(&self) -> Option<String> {
-        self.genre.clone()
-    }
-
-    pub fn id(&self) -> i64 {
-        self.id
-    }
-
-    pub fn kind(&self) -> ResourceKind {
-        self.kind
+        match self {
+            Self::Track(track) => track.genre(),
+            Self::User(_) => None,
+            Self::Playlist(playlist) => playlist.genre(),
+        }
     }
 
+    #[must_use]
     pub fn likes_count(&self) -> Option<i64> {
-        self.likes_count
+        match self {
+            Self::Track(track) => track.likes_count(),
+            Self::User(_) => None,
+            Self::Playlist(playlist) => playlist.likes_count(),
+        }
     }
 
+    #[must_use]
     pub fn permalink_url(&self) -> Option<String> {
-        self.permalink_url.clone()
+        match self {
+            Self::Track(track) => track.permalink_url(),
+            Self::User(user) => user.permalink_url(),
+            Self::Playlist(playlist) => playlist.permalink_url(),
+        }
     }
 
+    #[must_use]
     pub fn playback_count(&self) -> Option<i64> {
-        self.playback_count.clone()
+        self.as_track().and_then(Track::playback_count)
     }
 
+    /// Tags joined back into the API's space-delimited form.
+    #[must_use]
     pub fn tag_list(&self) -> Option<String> {
-        self.tag_list.clone()
+        let tags = match self {
+            Self::Track(track) => track.tag_list(),
+            Self::User(_) => return None,
+            Self::Playlist(playlist) => playlist.tag_list(),
+        };
+        Some(format_tag_list(&tags))
     }
 
+    #[must_use]
     pub fn title(&self) -> Option<String> {
-        self.title.clone()
+        match self {
+            Self::Track(track) => track.title(),
+            Self::User(_) => None,
+            Self::Playlist(playlist) => playlist.title(),
+        }
     }
 
+    #[must_use]
     pub fn tracks(&self) -> Option<Vec<Resource>> {
-        self.tracks.clone()
+        self.as_playlist()
+            .and_then(Playlist::tracks)
+            .map(|tracks| tracks.into_iter().map(Resource::Track).collect())
     }
 
+    #[must_use]
     pub fn media(&self) -> Option<Media> {
-        self.media.clone()
+        self.as_track().and_then(Track::media)
     }
 
+    #[must_use]
     pub fn user(&self) -> Option<Resource> {
-        self.user.clone().map(|user| *user)
+        let user = match self {
+            Self::Track(track) => track.user(),
+            Self::User(_) => None,
+            Self::Playlist(playlist) => playlist.user(),
+        };
+        user.map(Resource::User)
     }
 
+    #[must_use]
     pub fn username(&self) -> Option<String> {
-        self.username.clone()
+        self.as_user().and_then(User::username)
     }
 }
 
@@ -115,14 +213,81 @@ pub struct Media {
 }
 
 impl Media {
+    #[must_use]
     pub fn transcodings(&self) -> Vec<Transcoding> {
         self.transcodings.clone()
     }
 
+    #[must_use]
     pub fn progressive(&self) -> Option<Transcoding> {
         self.transcodings()
             .into_iter()
-            .find(|transcoding| transcoding.format().protocol().to_string() == "progressive")
+            .find(|transcoding| transcoding.format().protocol() == "progressive")
+    }
+
+    /// Picks the transcoding `policy` ranks highest, if any is supported.
+    #[must_use]
+    pub fn select(&self, policy: &TranscodingPolicy) -> Option<Transcoding> {
+        policy.select(&self.transcodings)
+    }
+}
+
+/// Protocol and codec combinations a [`Transcoding`] can be offered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscodingKind {
+    ProgressiveMp3,
+    HlsMp3,
+    HlsAac,
+    HlsOpus,
+}
+
+/// Order in which transcodings are preferred, skipping kinds not listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscodingPolicy {
+    order: Vec<TranscodingKind>,
+}
+
+impl TranscodingPolicy {
+    #[must_use]
+    pub fn new(order: Vec<TranscodingKind>) -> Self {
+        Self { order }
+    }
+
+    /// Progressive, then HLS mp3: only what a decoder without AAC or Opus
+    /// support, such as `rodio`'s defaults, can play.
+    #[must_use]
+    pub fn mp3() -> Self {
+        Self::new(vec![
+            TranscodingKind::ProgressiveMp3,
+            TranscodingKind::HlsMp3,
+        ])
+    }
+
+    #[must_use]
+    pub fn order(&self) -> Vec<TranscodingKind> {
+        self.order.clone()
+    }
+
+    #[must_use]
+    pub fn select(&self, transcodings: &[Transcoding]) -> Option<Transcoding> {
+        self.order.iter().find_map(|kind| {
+            transcodings
+                .iter()
+                .find(|transcoding| transcoding.kind() == Some(*kind))
+                .cloned()
+        })
+    }
+}
+
+/// Prefers progressive, then HLS mp3, then HLS AAC and finally HLS opus.
+impl Default for TranscodingPolicy {
+    fn default() -> Self {
+        Self::new(vec![
+            TranscodingKind::ProgressiveMp3,
+            TranscodingKind::HlsMp3,
+            TranscodingKind::HlsAac,
+            TranscodingKind::HlsOpus,
+        ])
     }
 }
 
@@ -136,21 +301,50 @@ pub struct Transcoding {
 }
 
 impl Transcoding {
+    #[must_use]
     pub fn url(&self) -> String {
         self.url.clone()
     }
 
+    #[must_use]
     pub fn preset(&self) -> String {
         self.preset.clone()
     }
 
+    #[must_use]
     pub fn duration(&self) -> i64 {
         self.duration
     }
 
+    #[must_use]
     pub fn format(&self) -> TranscodingFormat {
         self.format.clone()
     }
+
+    #[must_use]
+    pub fn quality(&self) -> String {
+        self.quality.clone()
+    }
+
+    /// Returns `None` for protocol and codec combinations we cannot play.
+    #[must_use]
+    pub fn kind(&self) -> Option<TranscodingKind> {
+        let mime_type = self.format.mime_type.as_str();
+        match self.format.protocol.as_str() {
+            "progressive" if mime_type.starts_with("audio/mpeg") => {
+                Some(TranscodingKind::ProgressiveMp3)
+            }
+            "hls" if mime_type.starts_with("audio/mpeg") => Some(TranscodingKind::HlsMp3),
+            "hls" if mime_type.starts_with("audio/mp4") => Some(TranscodingKind::HlsAac),
+            "hls" if mime_type.starts_with("audio/ogg") => Some(TranscodingKind::HlsOpus),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn is_hls(&self) -> bool {
+        self.format.protocol == "hls"
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -160,26 +354,33 @@ pub struct TranscodingFormat {
 }
 
 impl TranscodingFormat {
+    #[must_use]
     pub fn protocol(&self) -> String {
         self.protocol.clone()
     }
 
+    #[must_use]
     pub fn mime_type(&self) -> String {
         self.mime_type.clone()
     }
 
+    #[must_use]
     pub fn file_extension(&self) -> FileExtension {
-        if self.mime_type.starts_with("audio/mp4; codecs=\"mp4a\"") {
+        if self.mime_type.starts_with("audio/mp4") {
             FileExtension::M4A
+        } else if self.mime_type.starts_with("audio/ogg") {
+            FileExtension::OPUS
         } else {
             FileExtension::MP3
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileExtension {
     M4A,
     MP3,
+    OPUS,
 }
 
 impl std::fmt::Display for FileExtension {
@@ -187,6 +388,7 @@ impl std::fmt::Display for FileExtension {
         match &self {
             Self::M4A => write!(f, ".m4a"),
             Self::MP3 => write!(f, ".mp3"),
+            Self::OPUS => write!(f, ".opus"),
         }
     }
 }
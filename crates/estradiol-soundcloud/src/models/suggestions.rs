@@ -0,0 +1,22 @@
+use serde::Deserialize;
+
+/// A completion of a partly typed search query.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SearchSuggestion {
+    output: String,
+    query: String,
+}
+
+impl SearchSuggestion {
+    /// The completion as it is shown.
+    #[must_use]
+    pub fn output(&self) -> String {
+        self.output.clone()
+    }
+
+    /// The query to search for when the completion is picked.
+    #[must_use]
+    pub fn query(&self) -> String {
+        self.query.clone()
+    }
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use super::{
+    de,
+    resources::{Media, Transcoding, TranscodingPolicy},
+    users::User,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Track {
+    id: i64,
+    title: Option<String>,
+    artwork_url: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "de::duration_ms")]
+    duration: Option<Duration>,
+    #[serde(default, deserialize_with = "de::duration_ms")]
+    full_duration: Option<Duration>,
+    genre: Option<String>,
+    likes_count: Option<i64>,
+    permalink_url: Option<String>,
+    playback_count: Option<i64>,
+    #[serde(default, deserialize_with = "de::tag_list")]
+    tag_list: Vec<String>,
+    media: Option<Media>,
+    user: Option<User>,
+    policy: Option<String>,
+    streamable: Option<bool>,
+}
+
+/// `policy` of tracks that are not available in the requester's region.
+const POLICY_BLOCK: &str = "BLOCK";
+
+impl Track {
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[must_use]
+    pub fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
+
+    #[must_use]
+    pub fn artwork_url(&self) -> Option<String> {
+        self.artwork_url.clone()
+    }
+
+    #[must_use]
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+
+    /// Playable duration, which is a preview's length for snipped tracks.
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    #[must_use]
+    pub fn full_duration(&self) -> Option<Duration> {
+        self.full_duration
+    }
+
+    #[must_use]
+    pub fn genre(&self) -> Option<String> {
+        self.genre.clone()
+    }
+
+    #[must_use]
+    pub fn likes_count(&self) -> Option<i64> {
+        self.likes_count
+    }
+
+    #[must_use]
+    pub fn permalink_url(&self) -> Option<String> {
+        self.permalink_url.clone()
+    }
+
+    #[must_use]
+    pub fn playback_count(&self) -> Option<i64> {
+        self.playback_count
+    }
+
+    #[must_use]
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tag_list.clone()
+    }
+
+    #[must_use]
+    pub fn media(&self) -> Option<Media> {
+        self.media.clone()
+    }
+
+    #[must_use]
+    pub fn user(&self) -> Option<User> {
+        self.user.clone()
+    }
+
+    /// Availability in the requester's region: `ALLOW`, `MONETIZE`, `SNIP`
+    /// (preview only) or `BLOCK`.
+    #[must_use]
+    pub fn policy(&self) -> Option<String> {
+        self.policy.clone()
+    }
+
+    #[must_use]
+    pub fn streamable(&self) -> Option<bool> {
+        self.streamable
+    }
+
+    /// Picks the transcoding to play this track with.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Unstreamable`] if the track is blocked in this
+    /// region, not streamable, or offers nothing `policy` accepts.
+    pub fn transcoding(&self, policy: &TranscodingPolicy) -> Result<Transcoding, crate::Error> {
+        let unstreamable = |reason: &str| crate::Error::Unstreamable {
+            id: self.id,
+            reason: String::from(reason),
+        };
+        if self.policy.as_deref() == Some(POLICY_BLOCK) {
+            return Err(unstreamable("blocked in this region"));
+        }
+        if self.streamable == Some(false) {
+            return Err(unstreamable("streaming is disabled"));
+        }
+        self.media
+            .as_ref()
+            .and_then(|media| media.select(policy))
+            .ok_or_else(|| unstreamable("no supported transcoding"))
+    }
+
+    /// Whether this is a stub track, carrying little more than its id.
+    #[must_use]
+    pub fn is_stub(&self) -> bool {
+        self.title.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::resources::TranscodingPolicy;
+
+    use super::Track;
+
+    #[test]
+    fn test_transcoding_unstreamable() {
+        let blocked: Track =
+            serde_json::from_str(r#"{"id": 1, "title": "x", "policy": "BLOCK"}"#).unwrap();
+        let disabled: Track =
+            serde_json::from_str(r#"{"id": 2, "title": "x", "streamable": false}"#).unwrap();
+        let no_media: Track = serde_json::from_str(r#"{"id": 3, "title": "x"}"#).unwrap();
+
+        for track in [blocked, disabled, no_media] {
+            let err = track
+                .transcoding(&TranscodingPolicy::default())
+                .unwrap_err();
+            assert!(matches!(err, crate::Error::Unstreamable { id, .. } if id == track.id()));
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct User {
+    id: i64,
+    username: Option<String>,
+    avatar_url: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    followers_count: Option<i64>,
+    followings_count: Option<i64>,
+    permalink_url: Option<String>,
+}
+
+impl User {
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[must_use]
+    pub fn username(&self) -> Option<String> {
+        self.username.clone()
+    }
+
+    #[must_use]
+    pub fn avatar_url(&self) -> Option<String> {
+        self.avatar_url.clone()
+    }
+
+    #[must_use]
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+
+    #[must_use]
+    pub fn followers_count(&self) -> Option<i64> {
+        self.followers_count
+    }
+
+    #[must_use]
+    pub fn followings_count(&self) -> Option<i64> {
+        self.followings_count
+    }
+
+    #[must_use]
+    pub fn permalink_url(&self) -> Option<String> {
+        self.permalink_url.clone()
+    }
+}
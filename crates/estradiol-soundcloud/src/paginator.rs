@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+use serde::de::DeserializeOwned;
+
+use crate::{models::collections::Collection, Client};
+
+/// Lazily walks a collection endpoint, following `next_href` page by page.
+///
+/// Yields each item of the current page before fetching the next one. Stops
+/// after the last page, after `max_items` items or after the first error.
+#[derive(Debug)]
+pub struct Paginator<'a, T> {
+    client: &'a Client,
+    items: VecDeque<T>,
+    next_href: Option<String>,
+    max_items: Option<usize>,
+    yielded: usize,
+    done: bool,
+}
+
+impl<'a, T: DeserializeOwned> Paginator<'a, T> {
+    pub(crate) fn new(client: &'a Client, first: Collection<T>) -> Self {
+        let next_href = first.next_href();
+        Self {
+            client,
+            items: first.into_collection().into(),
+            next_href,
+            max_items: None,
+            yielded: 0,
+            done: false,
+        }
+    }
+
+    /// Stops after `max_items` items, without fetching pages beyond them.
+    #[must_use]
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Returns the `next_href` of the last fetched page, if any.
+    #[must_use]
+    pub fn next_href(&self) -> Option<&str> {
+        self.next_href.as_deref()
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for Paginator<'_, T> {
+    type Item = Result<T, crate::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.max_items.is_some_and(|max| self.yielded >= max) {
+            return None;
+        }
+
+        while self.items.is_empty() {
+            let Some(next_href) = self.next_href.take() else {
+                self.done = true;
+                return None;
+            };
+            match self.client.next_page::<T>(&next_href) {
+                Ok(page) => {
+                    self.next_href = page.next_href();
+                    self.items = page.into_collection().into();
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        self.yielded += 1;
+        self.items.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::resources::Resource, testing::FixtureServer};
+
+    #[test]
+    fn test_paginate_follows_next_href() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+
+        let first = client.search("undertale", 50, 0)?;
+        let resources = client
+            .paginate(first)
+            .collect::<Result<Vec<Resource>, _>>()?;
+        let titles: Vec<_> = resources
+            .iter()
+            .filter_map(Resource::title)
+            .collect::<Vec<_>>();
+
+        assert_eq!(resources.len(), 5);
+        assert_eq!(titles.last().unwrap(), "Hopes and Dreams");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paginate_max_items() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+
+        let first = client.search("undertale", 50, 0)?;
+        let mut paginator = client.paginate(first).max_items(2);
+
+        assert!(paginator.next().is_some());
+        assert!(paginator.next().is_some());
+        assert!(paginator.next().is_none());
+        assert!(paginator.next_href().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paginate_stops_after_error() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+
+        let first = client.search("unavailable", 50, 0)?;
+        let results: Vec<_> = client.paginate(first).collect();
+
+        assert_eq!(results.len(), 4);
+        assert!(results[..3].iter().all(Result::is_ok));
+        assert!(results[3].is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_collection() -> Result<(), crate::Error> {
+        let server = FixtureServer::soundcloud();
+        let client = server.client();
+
+        let first = client.search("undertale", 50, 0)?;
+        let second = client.next_collection(&first)?.unwrap();
+
+        assert_eq!(second.collection().len(), 2);
+        assert!(client.next_collection(&second)?.is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+//! Retrying failed requests with backoff, and pacing requests so batch jobs
+//! don't trip the server's rate limits in the first place.
+
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// How a [`Client`](crate::Client) retries requests that fail with a
+/// retryable error: connection failures, 5xx and 429 responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after it.
+    pub base_delay: Duration,
+    /// Cap on the backoff delay. A server asking with `Retry-After` to wait
+    /// longer than this isn't retried at all.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retries.
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (from `0`): the server's
+    /// `Retry-After` if it sent one, otherwise exponential backoff with
+    /// jitter, so clients failing together don't retry together.
+    #[must_use]
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after;
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        backoff / 2 + backoff.mul_f64(fastrand::f64() / 2.0)
+    }
+
+    /// How long to wait before retrying after `err` on retry number
+    /// `attempt`, or `None` to give up: the error isn't retryable, retries
+    /// ran out, or the server asked to wait longer than `max_delay`.
+    pub(crate) fn wait(&self, attempt: u32, err: &crate::Error) -> Option<Duration> {
+        if !err.is_retryable() || attempt >= self.max_retries {
+            return None;
+        }
+        let retry_after = match err {
+            crate::Error::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        };
+        if retry_after.is_some_and(|retry_after| retry_after > self.max_delay) {
+            return None;
+        }
+        Some(self.delay(attempt, retry_after))
+    }
+
+    /// Runs `request`, sleeping and sending it again after retryable
+    /// failures as this policy allows.
+    pub(crate) fn run<T>(
+        &self,
+        request: impl Fn() -> Result<T, crate::Error>,
+    ) -> Result<T, crate::Error> {
+        let mut attempt = 0;
+        loop {
+            match request() {
+                Err(err) => match self.wait(attempt, &err) {
+                    Some(wait) => {
+                        std::thread::sleep(wait);
+                        attempt += 1;
+                    }
+                    None => return Err(err),
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+/// Requests per second a [`Client`](crate::Client) sends at most, on
+/// average, after an initial burst.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    /// Requests that may go out back to back before pacing kicks in.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Slowest rate a client paces requests at, one every 100 seconds.
+    const MIN_REQUESTS_PER_SECOND: f64 = 0.01;
+
+    /// Raises the rate to [`Self::MIN_REQUESTS_PER_SECOND`] if it is lower,
+    /// not positive or NaN, none of which the limiter can pace by.
+    pub(crate) fn clamped(self) -> Self {
+        Self {
+            requests_per_second: self.requests_per_second.max(Self::MIN_REQUESTS_PER_SECOND),
+            ..self
+        }
+    }
+}
+
+/// Token bucket enforcing a [`RateLimit`] across threads.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(Bucket {
+                tokens: f64::from(limit.burst),
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Blocks until a request may be sent.
+    pub(crate) fn acquire(&self) {
+        if let Some(wait) = self.reserve() {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Takes a token, returning how long the caller must wait before
+    /// sending its request, if at all.
+    pub(crate) fn reserve(&self) -> Option<Duration> {
+        let mut bucket = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.limit.requests_per_second)
+            .min(f64::from(self.limit.burst.max(1)));
+        bucket.refilled_at = now;
+
+        // Taking the token up front reserves this caller's slot, so
+        // concurrent callers queue up behind it instead of racing.
+        bucket.tokens -= 1.0;
+        (bucket.tokens < 0.0)
+            .then(|| Duration::from_secs_f64(-bucket.tokens / self.limit.requests_per_second))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{RateLimit, RateLimiter, RetryPolicy};
+
+    #[test]
+    fn test_delay_backs_off_with_jitter() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+        };
+
+        for attempt in 0..5 {
+            let backoff = Duration::from_millis(100 * 2_u64.pow(attempt)).min(policy.max_delay);
+            let delay = policy.delay(attempt, None);
+            assert!(delay >= backoff / 2 && delay <= backoff, "{delay:?}");
+        }
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(30))),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_paces_after_burst() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_second: 50.0,
+            burst: 2,
+        });
+
+        let start = Instant::now();
+        limiter.acquire();
+        limiter.acquire();
+        assert!(start.elapsed() < Duration::from_millis(15));
+        limiter.acquire();
+        limiter.acquire();
+        assert!(start.elapsed() >= Duration::from_millis(35));
+    }
+
+    #[test]
+    fn test_long_retry_after_not_waited_for() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(8),
+        };
+        let rate_limited = |seconds| crate::Error::RateLimited {
+            url: String::from("https://api-v2.soundcloud.com/tracks/1"),
+            retry_after: Some(Duration::from_secs(seconds)),
+        };
+
+        assert_eq!(
+            policy.wait(0, &rate_limited(7)),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(policy.wait(0, &rate_limited(86_400)), None);
+        assert_eq!(policy.wait(3, &rate_limited(1)), None);
+    }
+
+    #[test]
+    fn test_rate_limit_clamped() {
+        for requests_per_second in [0.0, -1.0, f64::NAN, 1e-300] {
+            let limit = RateLimit {
+                requests_per_second,
+                burst: 1,
+            }
+            .clamped();
+
+            assert!((limit.requests_per_second - 0.01).abs() < f64::EPSILON);
+            let limiter = RateLimiter::new(limit);
+            assert_eq!(limiter.reserve(), None);
+            assert!(limiter.reserve().is_some());
+        }
+    }
+}
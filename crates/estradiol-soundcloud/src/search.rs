@@ -0,0 +1,272 @@
+//! What to search for: which kind of resource, narrowed by the facets the
+//! search endpoints filter on.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Which search endpoint to query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    /// Tracks, users and playlists mixed together.
+    #[default]
+    All,
+    Tracks,
+    Users,
+    Playlists,
+    Albums,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 5] = [
+        SearchKind::All,
+        SearchKind::Tracks,
+        SearchKind::Users,
+        SearchKind::Playlists,
+        SearchKind::Albums,
+    ];
+
+    /// Path of the endpoint, relative to the API root.
+    #[must_use]
+    pub fn path(self) -> &'static str {
+        match self {
+            SearchKind::All => "/search",
+            SearchKind::Tracks => "/search/tracks",
+            SearchKind::Users => "/search/users",
+            SearchKind::Playlists => "/search/playlists",
+            SearchKind::Albums => "/search/albums",
+        }
+    }
+}
+
+impl fmt::Display for SearchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchKind::All => write!(f, "Everything"),
+            SearchKind::Tracks => write!(f, "Tracks"),
+            SearchKind::Users => write!(f, "Users"),
+            SearchKind::Playlists => write!(f, "Playlists"),
+            SearchKind::Albums => write!(f, "Albums"),
+        }
+    }
+}
+
+/// Track length buckets, as the search facets define them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationFilter {
+    /// Under 2 minutes.
+    Short,
+    /// 2 to 10 minutes.
+    Medium,
+    /// 10 to 30 minutes.
+    Long,
+    /// Over 30 minutes.
+    Epic,
+}
+
+impl DurationFilter {
+    pub const ALL: [DurationFilter; 4] = [
+        DurationFilter::Short,
+        DurationFilter::Medium,
+        DurationFilter::Long,
+        DurationFilter::Epic,
+    ];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DurationFilter::Short => "short",
+            DurationFilter::Medium => "medium",
+            DurationFilter::Long => "long",
+            DurationFilter::Epic => "epic",
+        }
+    }
+}
+
+impl fmt::Display for DurationFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DurationFilter::Short => write!(f, "Under 2 min"),
+            DurationFilter::Medium => write!(f, "2-10 min"),
+            DurationFilter::Long => write!(f, "10-30 min"),
+            DurationFilter::Epic => write!(f, "Over 30 min"),
+        }
+    }
+}
+
+/// How recently results were uploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CreatedAtFilter {
+    LastHour,
+    LastDay,
+    LastWeek,
+    LastMonth,
+    LastYear,
+}
+
+impl CreatedAtFilter {
+    pub const ALL: [CreatedAtFilter; 5] = [
+        CreatedAtFilter::LastHour,
+        CreatedAtFilter::LastDay,
+        CreatedAtFilter::LastWeek,
+        CreatedAtFilter::LastMonth,
+        CreatedAtFilter::LastYear,
+    ];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CreatedAtFilter::LastHour => "last_hour",
+            CreatedAtFilter::LastDay => "last_day",
+            CreatedAtFilter::LastWeek => "last_week",
+            CreatedAtFilter::LastMonth => "last_month",
+            CreatedAtFilter::LastYear => "last_year",
+        }
+    }
+}
+
+impl fmt::Display for CreatedAtFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CreatedAtFilter::LastHour => write!(f, "Past hour"),
+            CreatedAtFilter::LastDay => write!(f, "Past day"),
+            CreatedAtFilter::LastWeek => write!(f, "Past week"),
+            CreatedAtFilter::LastMonth => write!(f, "Past month"),
+            CreatedAtFilter::LastYear => write!(f, "Past year"),
+        }
+    }
+}
+
+/// What the uploader allows others to do with a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseFilter {
+    ToListen,
+    ToShare,
+    ToUseCommercially,
+    ToModifyCommercially,
+}
+
+impl LicenseFilter {
+    pub const ALL: [LicenseFilter; 4] = [
+        LicenseFilter::ToListen,
+        LicenseFilter::ToShare,
+        LicenseFilter::ToUseCommercially,
+        LicenseFilter::ToModifyCommercially,
+    ];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LicenseFilter::ToListen => "to_listen",
+            LicenseFilter::ToShare => "to_share",
+            LicenseFilter::ToUseCommercially => "to_use_commercially",
+            LicenseFilter::ToModifyCommercially => "to_modify_commercially",
+        }
+    }
+}
+
+impl fmt::Display for LicenseFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LicenseFilter::ToListen => write!(f, "To listen"),
+            LicenseFilter::ToShare => write!(f, "To share"),
+            LicenseFilter::ToUseCommercially => write!(f, "To use commercially"),
+            LicenseFilter::ToModifyCommercially => write!(f, "To modify commercially"),
+        }
+    }
+}
+
+/// Narrows a search. Filters left as `None` don't apply; the track facets
+/// (genre, duration and license) are ignored when searching users.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Matches the genre or any tag.
+    pub genre: Option<String>,
+    pub duration: Option<DurationFilter>,
+    pub created_at: Option<CreatedAtFilter>,
+    pub license: Option<LicenseFilter>,
+}
+
+impl SearchFilters {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The `filter.*` query parameters for these filters.
+    #[must_use]
+    pub fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        if let Some(genre) = self.genre.as_deref().filter(|genre| !genre.is_empty()) {
+            pairs.push(("filter.genre_or_tag", String::from(genre)));
+        }
+        if let Some(duration) = self.duration {
+            pairs.push(("filter.duration", String::from(duration.as_str())));
+        }
+        if let Some(created_at) = self.created_at {
+            pairs.push(("filter.created_at", String::from(created_at.as_str())));
+        }
+        if let Some(license) = self.license {
+            pairs.push(("filter.license", String::from(license.as_str())));
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_pairs() {
+        assert!(SearchFilters::default().query_pairs().is_empty());
+        let filters = SearchFilters {
+            genre: Some(String::from("Electronic")),
+            duration: Some(DurationFilter::Medium),
+            created_at: Some(CreatedAtFilter::LastWeek),
+            license: Some(LicenseFilter::ToShare),
+        };
+        assert_eq!(
+            filters.query_pairs(),
+            vec![
+                ("filter.genre_or_tag", String::from("Electronic")),
+                ("filter.duration", String::from("medium")),
+                ("filter.created_at", String::from("last_week")),
+                ("filter.license", String::from("to_share")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_genre_is_no_filter() {
+        let filters = SearchFilters {
+            genre: Some(String::new()),
+            ..SearchFilters::default()
+        };
+        assert!(filters.query_pairs().is_empty());
+    }
+
+    #[test]
+    fn test_filters_round_trip() -> Result<(), serde_json::Error> {
+        let filters = SearchFilters {
+            duration: Some(DurationFilter::Long),
+            license: Some(LicenseFilter::ToUseCommercially),
+            ..SearchFilters::default()
+        };
+
+        let json = serde_json::to_string(&filters)?;
+
+        assert!(json.contains(r#""license":"to_use_commercially""#));
+        assert_eq!(serde_json::from_str::<SearchFilters>(&json)?, filters);
+        assert_eq!(
+            serde_json::from_str::<SearchFilters>("{}")?,
+            SearchFilters::default()
+        );
+        Ok(())
+    }
+}
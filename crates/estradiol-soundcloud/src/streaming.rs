@@ -0,0 +1,578 @@
+//! A seekable reader over a remote file, fetched in HTTP range requests by a
+//! background thread that stays a bounded distance ahead of the reader.
+
+use std::{
+    io::{Read, Seek, SeekFrom},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+use ureq::Agent;
+
+use crate::retry::RetryPolicy;
+
+/// Tuning for a [`StreamReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// Bytes fetched per range request, raised to 1 if zero.
+    pub chunk_size: u64,
+    /// Bytes buffered ahead of the read position before fetching pauses.
+    pub read_ahead: u64,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 256 * 1024,
+            read_ahead: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// A copy of the file kept as it is fetched, handed on once it is whole.
+struct Recording {
+    bytes: Vec<u8>,
+    on_complete: Box<dyn FnOnce(Vec<u8>) + Send>,
+}
+
+impl std::fmt::Debug for Recording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recording")
+            .field("bytes", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Offset of `data[0]` in the file.
+    start: u64,
+    data: Vec<u8>,
+    /// Where the reader is, so the fetcher knows how far ahead it is.
+    position: u64,
+    len: Option<u64>,
+    /// Bumped on every reposition so in-flight chunks for a stale offset are dropped.
+    generation: u64,
+    error: Option<String>,
+    closed: bool,
+    recording: Option<Recording>,
+}
+
+impl State {
+    fn end(&self) -> u64 {
+        self.start + self.data.len() as u64
+    }
+
+    fn is_complete(&self) -> bool {
+        self.len.is_some_and(|len| self.end() >= len)
+    }
+
+    fn append(&mut self, chunk: &Chunk, keep_behind: u64) {
+        if let Some(len) = chunk.len {
+            self.len = Some(len);
+        }
+        let offset = self.end();
+        if let Some(recording) = &mut self.recording {
+            // Only what continues the copy counts; chunks past a gap left by
+            // a seek can't be placed in it.
+            let recorded = recording.bytes.len() as u64;
+            let end = offset + chunk.bytes.len() as u64;
+            if (offset..end).contains(&recorded) {
+                let skip = usize::try_from(recorded - offset).unwrap_or(usize::MAX);
+                recording.bytes.extend_from_slice(&chunk.bytes[skip..]);
+            }
+        }
+        self.data.extend_from_slice(&chunk.bytes);
+        if chunk.bytes.is_empty() && self.len.is_none() {
+            self.len = Some(self.end());
+        }
+
+        // Forget what the reader is well past, so long tracks don't pile up in memory.
+        let behind = self.position.saturating_sub(self.start);
+        if behind > keep_behind {
+            let drop = usize::try_from(behind - keep_behind).unwrap_or(usize::MAX);
+            let drop = drop.min(self.data.len());
+            self.data.drain(..drop);
+            self.start += drop as u64;
+        }
+    }
+
+    /// Takes the recording once it holds the whole file.
+    fn finished_recording(&mut self) -> Option<Recording> {
+        let len = self.len?;
+        if self.recording.as_ref()?.bytes.len() as u64 >= len {
+            return self.recording.take();
+        }
+        None
+    }
+
+    fn reposition(&mut self, position: u64) {
+        self.start = position;
+        self.data.clear();
+        self.generation += 1;
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+struct Chunk {
+    bytes: Vec<u8>,
+    len: Option<u64>,
+}
+
+/// Reads a remote file as it downloads, issuing new range requests on seek.
+#[derive(Debug)]
+pub struct StreamReader {
+    shared: Arc<Shared>,
+    position: u64,
+    options: StreamOptions,
+}
+
+impl StreamReader {
+    /// Fetches the first chunk of `url`, then keeps fetching in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the first chunk cannot be fetched.
+    pub(crate) fn open(
+        agent: Agent,
+        url: &str,
+        options: StreamOptions,
+        retry: RetryPolicy,
+    ) -> Result<Self, crate::Error> {
+        let options = StreamOptions {
+            chunk_size: options.chunk_size.max(1),
+            ..options
+        };
+        let first = retry.run(|| fetch_chunk(&agent, url, 0, options.chunk_size))?;
+
+        let mut state = State::default();
+        state.append(&first, options.read_ahead);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(state),
+            changed: Condvar::new(),
+        });
+
+        {
+            let shared = Arc::clone(&shared);
+            let url = String::from(url);
+            std::thread::spawn(move || fetch_ahead(&agent, &url, &shared, options, retry));
+        }
+
+        Ok(Self {
+            shared,
+            position: 0,
+            options,
+        })
+    }
+
+    /// Total length, once the server has reported it.
+    #[must_use]
+    pub fn len(&self) -> Option<u64> {
+        self.shared.lock().len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Keeps a copy of the file as it is fetched and passes it to
+    /// `on_complete`, on the fetching thread, once every byte has arrived.
+    /// The copy is given up if the reader seeks past what was fetched
+    /// before the file is whole, or is dropped first.
+    pub fn record(&self, on_complete: impl FnOnce(Vec<u8>) + Send + 'static) {
+        let mut state = self.shared.lock();
+        if state.start != 0 {
+            return;
+        }
+        state.recording = Some(Recording {
+            bytes: state.data.clone(),
+            on_complete: Box::new(on_complete),
+        });
+        if let Some(recording) = state.finished_recording() {
+            drop(state);
+            (recording.on_complete)(recording.bytes);
+        }
+    }
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.shared.lock();
+        state.position = self.position;
+        loop {
+            if let Some(error) = state.error.take() {
+                // The fetcher waits while an error is pending; wake it to try again.
+                self.shared.changed.notify_all();
+                return Err(std::io::Error::other(error));
+            }
+            if state.len.is_some_and(|len| self.position >= len) {
+                return Ok(0);
+            }
+
+            let end = state.end();
+            if (state.start..end).contains(&self.position) {
+                let offset = usize::try_from(self.position - state.start).unwrap_or(usize::MAX);
+                let available = &state.data[offset..];
+                let read = available.len().min(buf.len());
+                buf[..read].copy_from_slice(&available[..read]);
+                self.position += read as u64;
+                state.position = self.position;
+                self.shared.changed.notify_all();
+                return Ok(read);
+            }
+
+            if self.position < state.start || self.position > end + self.options.read_ahead {
+                state.reposition(self.position);
+                self.shared.changed.notify_all();
+            }
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+        }
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => match self.len() {
+                Some(len) => len.checked_add_signed(delta),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Unsupported,
+                        "stream length is unknown",
+                    ))
+                }
+            },
+        };
+        let Some(position) = position else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek before start of stream",
+            ));
+        };
+
+        self.position = position;
+        Ok(position)
+    }
+}
+
+impl Drop for StreamReader {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+fn fetch_ahead(
+    agent: &Agent,
+    url: &str,
+    shared: &Shared,
+    options: StreamOptions,
+    retry: RetryPolicy,
+) {
+    let mut state = shared.lock();
+    loop {
+        if state.closed {
+            return;
+        }
+        let ahead = state.end().saturating_sub(state.position);
+        if state.is_complete() || state.error.is_some() || ahead >= options.read_ahead {
+            state = shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            continue;
+        }
+
+        let offset = state.end();
+        let generation = state.generation;
+        drop(state);
+
+        let chunk = retry.run(|| fetch_chunk(agent, url, offset, options.chunk_size));
+
+        state = shared.lock();
+        if state.generation != generation {
+            continue;
+        }
+        match chunk {
+            Ok(chunk) => state.append(&chunk, options.read_ahead),
+            Err(err) => state.error = Some(err.to_string()),
+        }
+        shared.changed.notify_all();
+        if let Some(recording) = state.finished_recording() {
+            drop(state);
+            (recording.on_complete)(recording.bytes);
+            state = shared.lock();
+        }
+    }
+}
+
+/// Fetches `size` bytes at `offset`, falling back to the whole body for
+/// servers that ignore `Range`.
+fn fetch_chunk(agent: &Agent, url: &str, offset: u64, size: u64) -> Result<Chunk, crate::Error> {
+    let range = format!("bytes={offset}-{}", offset + size - 1);
+
+    let res = match agent.get(url).set("Range", &range).call() {
+        Ok(res) => res,
+        Err(ureq::Error::Status(416, _)) => {
+            return Ok(Chunk {
+                bytes: Vec::new(),
+                len: Some(offset),
+            })
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if res.status() != 206 {
+        let len = res
+            .header("Content-Length")
+            .and_then(|len| len.parse::<u64>().ok());
+        let mut bytes = Vec::new();
+        res.into_reader().read_to_end(&mut bytes)?;
+        let skip = usize::try_from(offset)
+            .unwrap_or(usize::MAX)
+            .min(bytes.len());
+        bytes.drain(..skip);
+        return Ok(Chunk {
+            len: len.or(Some(offset + bytes.len() as u64)),
+            bytes,
+        });
+    }
+
+    let len = res
+        .header("Content-Range")
+        .and_then(|range| range.rsplit_once('/'))
+        .and_then(|(_, len)| len.parse::<u64>().ok());
+    let mut bytes = Vec::new();
+    res.into_reader().take(size).read_to_end(&mut bytes)?;
+
+    Ok(Chunk { bytes, len })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Seek, SeekFrom},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use crate::{
+        testing::{ranged, FixtureServer, Reply},
+        RetryPolicy,
+    };
+
+    use super::StreamOptions;
+
+    const TRACK: &[u8] = include_bytes!("../fixtures/track.mp3");
+    const OPTIONS: StreamOptions = StreamOptions {
+        chunk_size: 512,
+        read_ahead: 1024,
+    };
+
+    /// Serves the fixture mp3, recording each `Range` header received.
+    fn range_server(honor_range: bool) -> (FixtureServer, Arc<Mutex<Vec<String>>>) {
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let server = {
+            let ranges = Arc::clone(&ranges);
+            FixtureServer::start(move |request, _| {
+                if let Some(header) = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Range"))
+                {
+                    ranges.lock().unwrap().push(header.value.to_string());
+                }
+                if honor_range {
+                    ranged(request, TRACK)
+                } else {
+                    Reply::ok(TRACK)
+                }
+            })
+        };
+        (server, ranges)
+    }
+
+    #[test]
+    fn test_reads_whole_stream_in_chunks() -> Result<(), crate::Error> {
+        let (server, ranges) = range_server(true);
+        let client = server.client();
+
+        let mut reader = client.stream_reader(&format!("{}/track.mp3", server.url()), OPTIONS)?;
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        assert_eq!(bytes, TRACK);
+        assert_eq!(reader.len(), Some(TRACK.len() as u64));
+        assert_eq!(ranges.lock().unwrap()[0], "bytes=0-511");
+        assert!(ranges.lock().unwrap().len() >= TRACK.len().div_ceil(512));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_chunk_size_fetches_a_byte() -> Result<(), crate::Error> {
+        let (server, ranges) = range_server(true);
+        let client = server.client();
+        let options = StreamOptions {
+            chunk_size: 0,
+            ..OPTIONS
+        };
+
+        let mut reader = client.stream_reader(&format!("{}/track.mp3", server.url()), options)?;
+        let mut bytes = [0; 4];
+        reader.read_exact(&mut bytes)?;
+
+        assert_eq!(bytes, TRACK[..4]);
+        assert_eq!(ranges.lock().unwrap()[0], "bytes=0-0");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seek_issues_new_range_request() -> Result<(), crate::Error> {
+        let (server, ranges) = range_server(true);
+        let client = server.client();
+
+        let mut reader = client.stream_reader(&format!("{}/track.mp3", server.url()), OPTIONS)?;
+        let mut head = [0; 16];
+        reader.read_exact(&mut head)?;
+        reader.seek(SeekFrom::End(-100))?;
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail)?;
+        reader.seek(SeekFrom::Start(3))?;
+        let mut start = [0; 4];
+        reader.read_exact(&mut start)?;
+
+        assert_eq!(head, TRACK[..16]);
+        assert_eq!(tail, TRACK[TRACK.len() - 100..]);
+        assert_eq!(start, TRACK[3..7]);
+        assert!(ranges.lock().unwrap().contains(&format!(
+            "bytes={}-{}",
+            TRACK.len() - 100,
+            TRACK.len() + 411
+        )));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_records_whole_stream() -> Result<(), crate::Error> {
+        let (server, _) = range_server(true);
+        let client = server.client();
+
+        let mut reader = client.stream_reader(&format!("{}/track.mp3", server.url()), OPTIONS)?;
+        let (recorded_tx, recorded_rx) = std::sync::mpsc::channel();
+        reader.record(move |bytes| recorded_tx.send(bytes).unwrap());
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let recorded = recorded_rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(recorded, TRACK);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_server_without_range_support() -> Result<(), crate::Error> {
+        let (server, _) = range_server(false);
+        let client = server.client();
+
+        let mut reader = client.stream_reader(&format!("{}/track.mp3", server.url()), OPTIONS)?;
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        assert_eq!(bytes, TRACK);
+
+        Ok(())
+    }
+
+    /// Serves the fixture mp3, failing the third chunk once with a 503.
+    fn flaky_server() -> FixtureServer {
+        let failed = AtomicBool::new(false);
+        FixtureServer::start(move |request, _| {
+            let third_chunk = request
+                .headers()
+                .iter()
+                .any(|header| header.field.equiv("Range") && header.value == "bytes=1024-1535");
+            if third_chunk && !failed.swap(true, Ordering::SeqCst) {
+                Reply::status(503)
+            } else {
+                ranged(request, TRACK)
+            }
+        })
+    }
+
+    #[test]
+    fn test_reads_on_after_failed_chunk() -> Result<(), crate::Error> {
+        let server = flaky_server();
+        let client = server.builder().retry_policy(RetryPolicy::none()).build();
+
+        let mut reader = client.stream_reader(&format!("{}/track.mp3", server.url()), OPTIONS)?;
+        let mut bytes = Vec::new();
+        let mut errors = 0;
+        let mut buf = [0; 256];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => bytes.extend_from_slice(&buf[..read]),
+                Err(_) => errors += 1,
+            }
+        }
+
+        assert_eq!(errors, 1);
+        assert_eq!(bytes, TRACK);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retries_failed_chunk() -> Result<(), crate::Error> {
+        let server = flaky_server();
+        let client = server.client();
+
+        let mut reader = client.stream_reader(&format!("{}/track.mp3", server.url()), OPTIONS)?;
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        assert_eq!(bytes, TRACK);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_fails_without_first_chunk() {
+        let server = FixtureServer::start(|_, _| Reply::status(404));
+        let client = server.client();
+
+        assert!(client
+            .stream_reader(&format!("{}/missing.mp3", server.url()), OPTIONS)
+            .is_err());
+    }
+}
@@ -0,0 +1,516 @@
+//! Writing track metadata and artwork into audio files: `ID3v2` frames for
+//! MP3, `ilst` atoms for MP4/M4A.
+//!
+//! Works on any file, so tracks downloaded earlier can be retagged.
+
+use std::{ops::Range, path::Path};
+
+use chrono::Datelike;
+use id3::{
+    frame::{Comment, Content, Picture, PictureType},
+    Frame, TagLike, Version,
+};
+
+use crate::models::tracks::Track;
+
+/// Metadata to embed in a downloaded track.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<i32>,
+    pub permalink_url: Option<String>,
+    /// The track's tags, as a comment.
+    pub comment: Option<String>,
+    pub artwork: Option<Artwork>,
+}
+
+impl TrackTags {
+    /// Takes everything but the artwork from `track`; see
+    /// [`Client::artwork`](crate::Client::artwork) for that.
+    #[must_use]
+    pub fn from_track(track: &Track) -> Self {
+        let tag_list = track.tag_list();
+        Self {
+            title: track.title(),
+            artist: track.user().and_then(|user| user.username()),
+            genre: track.genre().filter(|genre| !genre.is_empty()),
+            year: track.created_at().map(|created_at| created_at.year()),
+            permalink_url: track.permalink_url(),
+            comment: (!tag_list.is_empty()).then(|| tag_list.join(", ")),
+            artwork: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artwork {
+    /// `image/jpeg` or `image/png`.
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Upgrades an artwork URL to its 500x500 variant: `…-large.jpg` becomes
+/// `…-t500x500.jpg`.
+#[must_use]
+pub fn artwork_url_500(url: &str) -> String {
+    let Some(dot) = url.rfind('.') else {
+        return String::from(url);
+    };
+    match url[..dot].rfind('-') {
+        Some(dash) if !url[dash..dot].contains('/') => {
+            format!("{}-t500x500{}", &url[..dash], &url[dot..])
+        }
+        _ => String::from(url),
+    }
+}
+
+/// Writes `tags` into the audio file at `path`, replacing the fields they
+/// set and keeping any others. The format is detected from the contents.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or written, or is neither
+/// MP3 nor MP4.
+pub fn write_tags(path: &Path, tags: &TrackTags) -> Result<(), crate::Error> {
+    let data = std::fs::read(path)?;
+    if data.get(4..8) == Some(b"ftyp") {
+        let tagged = write_mp4(&data, tags)?;
+        std::fs::write(path, tagged)?;
+        Ok(())
+    } else if data.starts_with(b"ID3") || data.starts_with(&[0xFF]) {
+        write_id3(path, tags)
+    } else {
+        Err(crate::Error::Tags(format!(
+            "{} is neither MP3 nor MP4",
+            path.display()
+        )))
+    }
+}
+
+fn write_id3(path: &Path, tags: &TrackTags) -> Result<(), crate::Error> {
+    let mut tag = id3::Tag::read_from_path(path).unwrap_or_default();
+    if let Some(title) = &tags.title {
+        tag.set_title(title);
+    }
+    if let Some(artist) = &tags.artist {
+        tag.set_artist(artist);
+    }
+    if let Some(genre) = &tags.genre {
+        tag.set_genre(genre);
+    }
+    if let Some(year) = tags.year {
+        tag.set_year(year);
+    }
+    if let Some(permalink_url) = &tags.permalink_url {
+        tag.remove("WOAS");
+        tag.add_frame(Frame::with_content(
+            "WOAS",
+            Content::Link(permalink_url.clone()),
+        ));
+    }
+    if let Some(comment) = &tags.comment {
+        tag.add_frame(Comment {
+            lang: String::from("eng"),
+            description: String::new(),
+            text: comment.clone(),
+        });
+    }
+    if let Some(artwork) = &tags.artwork {
+        tag.remove_picture_by_type(PictureType::CoverFront);
+        tag.add_frame(Picture {
+            mime_type: artwork.mime_type.clone(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: artwork.data.clone(),
+        });
+    }
+    tag.write_to_path(path, Version::Id3v24)
+        .map_err(|err| crate::Error::Tags(err.to_string()))
+}
+
+/// MP4 data atom type codes.
+const UTF8: u32 = 1;
+const JPEG: u32 = 13;
+const PNG: u32 = 14;
+
+/// A box found in an MP4 file, by byte range.
+#[derive(Debug, Clone)]
+struct Mp4Box {
+    kind: [u8; 4],
+    /// Header and contents.
+    range: Range<usize>,
+    /// Contents only.
+    body: Range<usize>,
+}
+
+fn invalid_mp4() -> crate::Error {
+    crate::Error::Tags(String::from("invalid MP4 box structure"))
+}
+
+/// Reads the big-endian `u32` at `data[at..]`.
+fn be32(data: &[u8], at: usize) -> Result<u32, crate::Error> {
+    let bytes = at
+        .checked_add(4)
+        .and_then(|end| data.get(at..end))
+        .ok_or_else(invalid_mp4)?;
+    Ok(u32::from_be_bytes(
+        bytes.try_into().map_err(|_| invalid_mp4())?,
+    ))
+}
+
+/// Lists the boxes laid end to end in `data[range]`.
+fn boxes(data: &[u8], range: Range<usize>) -> Result<Vec<Mp4Box>, crate::Error> {
+    let mut found = Vec::new();
+    let mut at = range.start;
+    while range.end.checked_sub(at).is_some_and(|left| left >= 8) {
+        let size = be32(data, at)? as usize;
+        let kind: [u8; 4] = data
+            .get(at + 4..at + 8)
+            .and_then(|kind| kind.try_into().ok())
+            .ok_or_else(invalid_mp4)?;
+        let (header, size) = match size {
+            0 => (8, range.end - at),
+            1 => {
+                let large = u64::from(be32(data, at + 8)?) << 32 | u64::from(be32(data, at + 12)?);
+                (16, usize::try_from(large).map_err(|_| invalid_mp4())?)
+            }
+            size => (8, size),
+        };
+        let end = at
+            .checked_add(size)
+            .filter(|&end| size >= header && end <= range.end)
+            .ok_or_else(invalid_mp4)?;
+        found.push(Mp4Box {
+            kind,
+            range: at..end,
+            body: at + header..end,
+        });
+        at = end;
+    }
+    Ok(found)
+}
+
+fn mp4_box(kind: [u8; 4], body: &[u8]) -> Vec<u8> {
+    let size = u32::try_from(body.len() + 8).unwrap_or(u32::MAX);
+    [&size.to_be_bytes()[..], &kind, body].concat()
+}
+
+fn data_atom(kind: [u8; 4], data_type: u32, payload: &[u8]) -> Vec<u8> {
+    let data = [&data_type.to_be_bytes()[..], &[0; 4], payload].concat();
+    mp4_box(kind, &mp4_box(*b"data", &data))
+}
+
+/// An iTunes-style `----` atom, for fields without an atom of their own.
+fn freeform_atom(name: &str, value: &str) -> Vec<u8> {
+    let mean = mp4_box(*b"mean", &[&[0; 4], &b"com.apple.iTunes"[..]].concat());
+    let name = mp4_box(*b"name", &[&[0; 4], name.as_bytes()].concat());
+    let data = mp4_box(
+        *b"data",
+        &[&UTF8.to_be_bytes()[..], &[0; 4], value.as_bytes()].concat(),
+    );
+    mp4_box(*b"----", &[mean, name, data].concat())
+}
+
+/// The `meta` box holding `tags` as an `ilst`.
+fn meta_box(tags: &TrackTags) -> Vec<u8> {
+    let text = [
+        (*b"\xa9nam", &tags.title),
+        (*b"\xa9ART", &tags.artist),
+        (*b"\xa9gen", &tags.genre),
+        (*b"\xa9cmt", &tags.comment),
+    ];
+    let mut items: Vec<u8> = text
+        .into_iter()
+        .filter_map(|(kind, value)| Some(data_atom(kind, UTF8, value.as_ref()?.as_bytes())))
+        .flatten()
+        .collect();
+    if let Some(year) = tags.year {
+        items.extend(data_atom(*b"\xa9day", UTF8, year.to_string().as_bytes()));
+    }
+    if let Some(permalink_url) = &tags.permalink_url {
+        items.extend(freeform_atom("URL", permalink_url));
+    }
+    if let Some(artwork) = &tags.artwork {
+        let data_type = if artwork.mime_type == "image/png" {
+            PNG
+        } else {
+            JPEG
+        };
+        items.extend(data_atom(*b"covr", data_type, &artwork.data));
+    }
+
+    let hdlr = mp4_box(
+        *b"hdlr",
+        &[&[0; 8], &b"mdirappl"[..], &[0; 8], &[0]].concat(),
+    );
+    let ilst = mp4_box(*b"ilst", &items);
+    mp4_box(*b"meta", &[&[0; 4], &hdlr[..], &ilst[..]].concat())
+}
+
+/// Returns `data` with `moov/udta/meta` replaced by one holding `tags`,
+/// shifting sample offsets past the `moov` by however much it grew.
+fn write_mp4(data: &[u8], tags: &TrackTags) -> Result<Vec<u8>, crate::Error> {
+    let top = boxes(data, 0..data.len())?;
+    let moov = top
+        .iter()
+        .find(|found| &found.kind == b"moov")
+        .ok_or_else(|| crate::Error::Tags(String::from("MP4 file has no moov box")))?;
+
+    let meta = meta_box(tags);
+    let mut body = Vec::with_capacity(moov.body.len() + meta.len());
+    let mut has_udta = false;
+    for child in boxes(data, moov.body.clone())? {
+        if &child.kind != b"udta" {
+            body.extend_from_slice(&data[child.range]);
+            continue;
+        }
+        has_udta = true;
+        let mut udta = Vec::new();
+        for entry in boxes(data, child.body)? {
+            if &entry.kind != b"meta" {
+                udta.extend_from_slice(&data[entry.range]);
+            }
+        }
+        udta.extend_from_slice(&meta);
+        body.extend(mp4_box(*b"udta", &udta));
+    }
+    if !has_udta {
+        body.extend(mp4_box(*b"udta", &meta));
+    }
+    let mut new_moov = mp4_box(*b"moov", &body);
+
+    let delta = i64::try_from(new_moov.len()).unwrap_or(i64::MAX)
+        - i64::try_from(moov.range.len()).unwrap_or(i64::MAX);
+    let mut rest = data[moov.range.end..].to_vec();
+    if delta != 0 {
+        let moved = moov.range.start as u64;
+        let len = new_moov.len();
+        shift_chunk_offsets(&mut new_moov, 0..len, moved, delta)?;
+        shift_fragment_offsets(&mut rest, moved, delta)?;
+    }
+
+    Ok([&data[..moov.range.start], &new_moov[..], &rest[..]].concat())
+}
+
+/// Shifts `stco`/`co64` chunk offsets at or past `from` under `data[range]`.
+fn shift_chunk_offsets(
+    data: &mut [u8],
+    range: Range<usize>,
+    from: u64,
+    delta: i64,
+) -> Result<(), crate::Error> {
+    for found in boxes(data, range)? {
+        match &found.kind {
+            b"moov" | b"trak" | b"mdia" | b"minf" | b"stbl" => {
+                shift_chunk_offsets(data, found.body, from, delta)?;
+            }
+            b"stco" | b"co64" => {
+                let width = if &found.kind == b"stco" { 4 } else { 8 };
+                // Version and flags, then the entry count, then the entries.
+                let count = be32(data, found.body.start + 4)? as usize;
+                let entries = found.body.start + 8;
+                let end = count
+                    .checked_mul(width)
+                    .and_then(|len| entries.checked_add(len))
+                    .filter(|&end| end <= found.body.end)
+                    .ok_or_else(invalid_mp4)?;
+                for at in (entries..end).step_by(width) {
+                    let field = data.get_mut(at..at + width).ok_or_else(invalid_mp4)?;
+                    shift_offset(field, from, delta)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Shifts explicit `tfhd` base data offsets in fragments after the `moov`.
+fn shift_fragment_offsets(data: &mut [u8], from: u64, delta: i64) -> Result<(), crate::Error> {
+    for moof in boxes(data, 0..data.len())? {
+        if &moof.kind != b"moof" {
+            continue;
+        }
+        for traf in boxes(data, moof.body)? {
+            if &traf.kind != b"traf" {
+                continue;
+            }
+            for tfhd in boxes(data, traf.body)? {
+                let flags = data.get(tfhd.body.start + 1..tfhd.body.start + 4);
+                let has_base_offset = flags.is_some_and(|flags| flags[2] & 1 == 1);
+                if &tfhd.kind == b"tfhd" && has_base_offset && tfhd.body.len() >= 16 {
+                    let at = tfhd.body.start + 8;
+                    shift_offset(&mut data[at..at + 8], from, delta)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Shifts the big-endian offset in `field` by `delta` if it points at or past `from`.
+fn shift_offset(field: &mut [u8], from: u64, delta: i64) -> Result<(), crate::Error> {
+    let overflow = || crate::Error::Tags(String::from("MP4 offsets overflow after tagging"));
+    let mut bytes = [0; 8];
+    bytes[8 - field.len()..].copy_from_slice(field);
+    let offset = u64::from_be_bytes(bytes);
+    if offset < from {
+        return Ok(());
+    }
+    let shifted = offset.checked_add_signed(delta).ok_or_else(overflow)?;
+    if field.len() == 4 {
+        let shifted = u32::try_from(shifted).map_err(|_| overflow())?;
+        field.copy_from_slice(&shifted.to_be_bytes());
+    } else {
+        field.copy_from_slice(&shifted.to_be_bytes());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use id3::TagLike;
+
+    use super::{artwork_url_500, boxes, mp4_box, write_tags, Artwork, TrackTags};
+
+    fn tags() -> TrackTags {
+        TrackTags {
+            title: Some(String::from("BIG SHOT")),
+            artist: Some(String::from("Toby Fox")),
+            genre: Some(String::from("Soundtrack")),
+            year: Some(2021),
+            permalink_url: Some(String::from("https://soundcloud.com/tobyfox/big-shot")),
+            comment: Some(String::from("deltarune, toby fox")),
+            artwork: Some(Artwork {
+                mime_type: String::from("image/jpeg"),
+                data: b"\xff\xd8\xff\xe0artwork".to_vec(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_artwork_url_500() {
+        assert_eq!(
+            artwork_url_500("https://i1.sndcdn.com/artworks-abc-def-large.jpg"),
+            "https://i1.sndcdn.com/artworks-abc-def-t500x500.jpg"
+        );
+        assert_eq!(
+            artwork_url_500("https://host/artworks/plain.jpg"),
+            "https://host/artworks/plain.jpg"
+        );
+    }
+
+    #[test]
+    fn test_write_id3() -> Result<(), crate::Error> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("track.mp3");
+        let audio = include_bytes!("../fixtures/track.mp3");
+        std::fs::write(&path, audio)?;
+
+        write_tags(&path, &tags())?;
+        // Retagging replaces the fields instead of piling up duplicates.
+        write_tags(&path, &tags())?;
+
+        let tag = id3::Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.title(), Some("BIG SHOT"));
+        assert_eq!(tag.artist(), Some("Toby Fox"));
+        assert_eq!(tag.year(), Some(2021));
+        assert_eq!(tag.pictures().count(), 1);
+        assert_eq!(tag.comments().count(), 1);
+        // The fixture starts with an empty tag header, which is replaced.
+        let bytes = std::fs::read(&path)?;
+        assert!(bytes.ends_with(&audio[10..]));
+
+        Ok(())
+    }
+
+    /// A minimal MP4 with one chunk offset pointing into the `mdat` after the `moov`.
+    fn mp4(offset_base: usize) -> Vec<u8> {
+        let ftyp = mp4_box(*b"ftyp", b"M4A \0\0\0\0");
+        let offset = u32::try_from(offset_base).unwrap();
+        let stco = mp4_box(
+            *b"stco",
+            &[&[0; 4], &1_u32.to_be_bytes()[..], &offset.to_be_bytes()].concat(),
+        );
+        let trak = mp4_box(
+            *b"trak",
+            &mp4_box(*b"mdia", &mp4_box(*b"minf", &mp4_box(*b"stbl", &stco))),
+        );
+        let moov = mp4_box(*b"moov", &trak);
+        [ftyp, moov, mp4_box(*b"mdat", b"samples")].concat()
+    }
+
+    #[test]
+    fn test_write_mp4_shifts_chunk_offsets() -> Result<(), crate::Error> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("track.m4a");
+        let mdat_body = mp4(0).len() - b"samples".len();
+        std::fs::write(&path, mp4(mdat_body))?;
+
+        write_tags(&path, &tags())?;
+        write_tags(&path, &tags())?;
+
+        let data = std::fs::read(&path)?;
+        let top = boxes(&data, 0..data.len())?;
+        let kinds: Vec<_> = top.iter().map(|found| &found.kind).collect();
+        assert_eq!(kinds, [b"ftyp", b"moov", b"mdat"]);
+
+        let stco_at = data.windows(4).position(|w| w == b"stco").unwrap();
+        let offset = u32::from_be_bytes(data[stco_at + 12..stco_at + 16].try_into().unwrap());
+        assert_eq!(&data[offset as usize..offset as usize + 7], b"samples");
+
+        let udta = boxes(&data, top[1].body.clone())?
+            .into_iter()
+            .find(|found| &found.kind == b"udta")
+            .unwrap();
+        let metas = boxes(&data, udta.body)?;
+        assert_eq!(metas.len(), 1);
+        assert!(data.windows(8).any(|w| w == b"BIG SHOT"));
+        assert!(data.windows(3).any(|w| w == b"URL"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_mp4_rejects_malformed_boxes() -> Result<(), crate::Error> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("track.m4a");
+        let ftyp = mp4_box(*b"ftyp", b"M4A \0\0\0\0");
+        // An stco claiming more entries than it holds.
+        let stco = mp4_box(
+            *b"stco",
+            &[&[0; 4], &1000_u32.to_be_bytes()[..], &[0; 4]].concat(),
+        );
+        let overcounted = [ftyp.clone(), mp4_box(*b"moov", &stco)].concat();
+        // A 64-bit box size running past the end of memory.
+        let oversized = [
+            ftyp,
+            [&1_u32.to_be_bytes()[..], b"free", &u64::MAX.to_be_bytes()].concat(),
+        ]
+        .concat();
+
+        for data in [overcounted, oversized] {
+            std::fs::write(&path, data)?;
+            assert!(matches!(
+                write_tags(&path, &tags()),
+                Err(crate::Error::Tags(_))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_tags_rejects_other_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("track.opus");
+        std::fs::write(&path, b"OggS").unwrap();
+
+        assert!(matches!(
+            write_tags(&path, &tags()),
+            Err(crate::Error::Tags(_))
+        ));
+    }
+}
@@ -0,0 +1,299 @@
+//! Local stand-in for the web, API and CDN hosts, serving recorded fixtures.
+
+use std::{sync::Arc, thread::JoinHandle, time::Duration};
+
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{Client, ClientBuilder, RetryPolicy};
+
+pub(crate) const CLIENT_ID: &str = "fixtureClientId0123456789abcdef";
+
+/// Stands in for the 500x500 artwork of the fixture track.
+pub(crate) const ARTWORK: &[u8] = b"\xff\xd8\xff\xe0fixture artwork";
+
+/// Retries without the default backoff, so failure tests stay quick.
+pub(crate) const FAST_RETRY: RetryPolicy = RetryPolicy {
+    max_retries: 2,
+    base_delay: Duration::from_millis(1),
+    max_delay: Duration::from_millis(5),
+};
+
+#[derive(Clone)]
+pub(crate) struct Reply {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((String::from(name), String::from(value)));
+        self
+    }
+}
+
+/// Splits a request URL into its path and decoded query pairs.
+pub(crate) fn split_url(url: &str) -> (String, Vec<(String, String)>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let pairs = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (String::from(key), decode(value))
+        })
+        .collect();
+    (String::from(path), pairs)
+}
+
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                out.push(u8::from_str_radix(hex, 16).unwrap_or(b'?'));
+                i += 2;
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub(crate) fn query<'a>(pairs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Serves requests on an ephemeral local port until dropped.
+pub(crate) struct FixtureServer {
+    server: Arc<Server>,
+    url: String,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl FixtureServer {
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&Request, &str) -> Reply + Send + 'static,
+    {
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("bind fixture server"));
+        let url = format!(
+            "http://{}",
+            server.server_addr().to_ip().expect("tcp fixture server")
+        );
+        let handle = {
+            let server = Arc::clone(&server);
+            let url = url.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    let reply = handler(&request, &url);
+                    let mut response =
+                        Response::from_data(reply.body).with_status_code(reply.status);
+                    for (name, value) in reply.headers {
+                        let header = Header::from_bytes(name.as_bytes(), value.as_bytes())
+                            .expect("valid fixture header");
+                        response.add_header(header);
+                    }
+                    let _ = request.respond(response);
+                }
+            })
+        };
+
+        Self {
+            server,
+            url,
+            handle: Some(handle),
+        }
+    }
+
+    /// Serves the recorded fixtures under `fixtures/`.
+    pub fn soundcloud() -> Self {
+        Self::start(soundcloud_fixtures)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// A builder pointed at this server for every host.
+    pub fn builder(&self) -> ClientBuilder {
+        ClientBuilder::new()
+            .web_origin(&self.url)
+            .api_v2(&self.url)
+            .cdn(&self.url)
+            .retry_policy(FAST_RETRY)
+    }
+
+    pub fn client(&self) -> Client {
+        self.builder().build()
+    }
+
+    #[cfg(feature = "async")]
+    pub fn async_client(&self) -> Result<crate::AsyncClient, crate::Error> {
+        self.builder().build_async()
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Serves `body` whole, or the slice a `Range: bytes=a-b` header asks for.
+pub(crate) fn ranged(request: &Request, body: &[u8]) -> Reply {
+    let range = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Range"))
+        .and_then(|header| header.value.as_str().strip_prefix("bytes="))
+        .and_then(|range| range.split_once('-'));
+    let Some((start, end)) = range else {
+        return Reply::ok(body.to_vec());
+    };
+
+    let len = body.len();
+    let start = start.parse::<usize>().unwrap_or(0);
+    let end = end.parse::<usize>().map_or(len - 1, |end| end.min(len - 1));
+    if start >= len {
+        return Reply::status(416).header("Content-Range", &format!("bytes */{len}"));
+    }
+
+    Reply {
+        status: 206,
+        headers: vec![(
+            String::from("Content-Range"),
+            format!("bytes {start}-{end}/{len}"),
+        )],
+        body: body[start..=end].to_vec(),
+    }
+}
+
+/// The fixture mp3 split into the three segments `hls_mp3.m3u8` lists.
+pub(crate) fn mp3_segments() -> Vec<&'static [u8]> {
+    let bytes = include_bytes!("../fixtures/track.mp3");
+    bytes.chunks(bytes.len().div_ceil(3)).collect()
+}
+
+/// Fills in the `{base}` placeholder recorded fixtures use for absolute URLs.
+pub(crate) fn fixture(body: &str, base: &str) -> Reply {
+    Reply::ok(body.replace("{base}", base)).header("Content-Type", "application/json")
+}
+
+pub(crate) fn soundcloud_fixtures(request: &Request, base: &str) -> Reply {
+    soundcloud_fixtures_for(request, base, CLIENT_ID)
+}
+
+/// Serves the fixtures with `client_id` as the only one the site hands out
+/// and the API accepts.
+pub(crate) fn soundcloud_fixtures_for(request: &Request, base: &str, client_id: &str) -> Reply {
+    let (path, pairs) = split_url(request.url());
+    let authorized = query(&pairs, "client_id") == Some(client_id);
+
+    match path.as_str() {
+        "/" => Reply::ok(include_str!("../fixtures/index.html").replace("{base}", base))
+            .header("Content-Type", "text/html"),
+        "/assets/0-fixture.js" => {
+            Reply::ok(include_str!("../fixtures/app.js").replace(CLIENT_ID, client_id))
+        }
+        "/artworks/big-shot-t500x500.jpg" => {
+            Reply::ok(ARTWORK.to_vec()).header("Content-Type", "image/jpeg")
+        }
+        "/stream/track.mp3" => ranged(request, include_bytes!("../fixtures/track.mp3"))
+            .header("Content-Type", "audio/mpeg"),
+        "/hls/playlist.m3u8" => Reply::ok(include_str!("../fixtures/hls_mp3.m3u8")),
+        "/hls/segment0.mp3" | "/hls/segment1.mp3" | "/hls/segment2.mp3" => {
+            let index = usize::from(path.as_bytes()[path.len() - 5] - b'0');
+            Reply::ok(mp3_segments()[index].to_vec()).header("Content-Type", "audio/mpeg")
+        }
+        "/hls/aac/playlist.m3u8" => Reply::ok(include_str!("../fixtures/hls_aac.m3u8")),
+        "/hls/aac/init.mp4" => Reply::ok("init"),
+        "/hls/aac/segment0.m4s" => Reply::ok("segment0"),
+        "/hls/aac/segment1.m4s" => Reply::ok("segment1"),
+        _ if !authorized => Reply::status(401),
+        "/tracks/1126821928" => fixture(include_str!("../fixtures/track.json"), base),
+        "/tracks/1126821928/comments" => fixture(include_str!("../fixtures/comments.json"), base),
+        "/tracks/1126821928/related" => fixture(include_str!("../fixtures/related.json"), base),
+        "/tracks/1126821928/albums" | "/search/albums" => {
+            fixture(include_str!("../fixtures/albums.json"), base)
+        }
+        "/tracks/1126821928/playlists" | "/users/3207/playlists" | "/search/playlists" => {
+            fixture(include_str!("../fixtures/playlists.json"), base)
+        }
+        "/tracks/1126821928/likers"
+        | "/tracks/1126821928/reposters"
+        | "/users/3207/followings"
+        | "/search/users" => fixture(include_str!("../fixtures/users.json"), base),
+        "/media/soundcloud:tracks:1126821928/progressive" => {
+            fixture(include_str!("../fixtures/stream.json"), base)
+        }
+        "/tracks/1126821941" => fixture(include_str!("../fixtures/track_hls.json"), base),
+        "/media/soundcloud:tracks:1126821941/hls" => {
+            fixture(include_str!("../fixtures/stream_hls_mp3.json"), base)
+        }
+        "/media/soundcloud:tracks:1126821941/hls-aac" => {
+            fixture(include_str!("../fixtures/stream_hls_aac.json"), base)
+        }
+        "/tracks" if query(&pairs, "ids") == Some("1126821941,1126821977") => {
+            fixture(include_str!("../fixtures/tracks.json"), base)
+        }
+        "/users/3207" => fixture(include_str!("../fixtures/user.json"), base),
+        "/users/3207/tracks" => fixture(include_str!("../fixtures/user_tracks.json"), base),
+        "/users/3207/likes" => fixture(include_str!("../fixtures/likes.json"), base),
+        "/playlists/1333360711" => fixture(include_str!("../fixtures/playlist.json"), base),
+        "/resolve" => match query(&pairs, "url") {
+            Some("https://soundcloud.com/tobyfox/big-shot") => {
+                fixture(include_str!("../fixtures/track.json"), base)
+            }
+            Some("https://soundcloud.com/tobyfox") => {
+                fixture(include_str!("../fixtures/user.json"), base)
+            }
+            _ => Reply::status(404),
+        },
+        "/search/tracks"
+            if query(&pairs, "filter.genre_or_tag") == Some("Electronic")
+                && query(&pairs, "filter.duration") == Some("medium") =>
+        {
+            fixture(include_str!("../fixtures/related.json"), base)
+        }
+        "/search/tracks" => fixture(r#"{"collection": []}"#, base),
+        "/search/queries" => fixture(include_str!("../fixtures/search_queries.json"), base),
+        "/search" => match (query(&pairs, "q"), query(&pairs, "offset")) {
+            (Some("unavailable"), Some("50")) => Reply::status(503),
+            (Some("unavailable"), _) => fixture(
+                &include_str!("../fixtures/search.json").replace("q=undertale", "q=unavailable"),
+                base,
+            ),
+            (_, Some("50")) => fixture(include_str!("../fixtures/search_page2.json"), base),
+            _ => fixture(include_str!("../fixtures/search.json"), base),
+        },
+        _ => Reply::status(404),
+    }
+}
@@ -1,9 +1,14 @@
-use crate::{apps::search::SearchApp, utils::Channel};
+use crate::{
+    apps::{queue::QueueApp, search::SearchApp, settings::SettingsApp},
+    utils::Channel,
+};
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub enum Anchor {
     #[default]
     Search,
+    Queue,
+    Settings,
 }
 
 impl std::fmt::Display for Anchor {
@@ -21,6 +26,8 @@ impl From<Anchor> for egui::WidgetText {
 #[derive(Debug)]
 pub struct AnchorState {
     pub search: SearchApp,
+    pub queue: QueueApp,
+    pub settings: SettingsApp,
     pub selected_anchor: Anchor,
 }
 
@@ -29,6 +36,8 @@ impl AnchorState {
         Self {
             selected_anchor: Anchor::Search,
             search: SearchApp::new(channel.clone()),
+            queue: QueueApp::new(channel.clone()),
+            settings: SettingsApp::new(channel.clone()),
         }
     }
 }
@@ -1,18 +1,95 @@
+use std::time::Duration;
+
+use estradiol_soundcloud::search::{SearchFilters, SearchKind};
+use serde::{Deserialize, Serialize};
+
 use crate::{
     anchor_state::{Anchor, AnchorState},
-    app_background::BackgroundEvent,
+    app_background::{BackgroundEvent, ErrorContext},
+    apps::{jobs::JobsIndicator, now_playing::NowPlayingBar, toasts::Toasts},
+    jobs::RequestId,
+    queue::{QueueItem, RepeatMode},
     utils::Channel,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum UiEvent {
-    SearchSubmit(String),
-    PlayTrack(i64),
+    SearchSubmit(SearchQuery),
+    /// Fetches completions of a partly typed query.
+    SearchSuggest(String),
+    /// Fetches the page at `next_href`, following the results of `search`.
+    SearchMore {
+        search: RequestId,
+        next_href: String,
+    },
+    /// Plays the track now, inserting it after the current one in the queue.
+    PlayTrack(QueueItem),
+    Enqueue(QueueItem),
+    PlayNext(QueueItem),
+    /// Saves the track to the download directory.
+    Download(QueueItem),
+    QueueRemove(usize),
+    QueueMove {
+        from: usize,
+        to: usize,
+    },
+    QueueJump(usize),
+    NextTrack,
+    PreviousTrack,
+    SetShuffle(bool),
+    SetRepeat(RepeatMode),
+    Pause,
+    Resume,
+    Stop,
+    Seek(Duration),
+    /// Linear gain, where `1.0` is the track's own volume.
+    SetVolume(f32),
+    /// Shows or hides the playing track on Discord.
+    SetDiscordPresence(bool),
+}
+
+/// A search as submitted: the text, what kind of results and their filters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchQuery {
+    pub text: String,
+    pub kind: SearchKind,
+    pub filters: SearchFilters,
+}
+
+impl SearchQuery {
+    /// A search for anything matching `text`, unfiltered.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: String::from(text),
+            ..Self::default()
+        }
+    }
+}
+
+impl std::fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\"", self.text)?;
+        if self.kind != SearchKind::All {
+            write!(f, " in {}", self.kind.to_string().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`UiEvent`] tagged with the id the background's responses carry.
+#[derive(Debug)]
+pub struct Request {
+    pub id: RequestId,
+    pub event: UiEvent,
 }
 
 #[derive(Debug)]
 pub struct App {
     anchor_state: AnchorState,
+    now_playing: NowPlayingBar,
+    toasts: Toasts,
+    jobs: JobsIndicator,
     channel: Channel,
 }
 
@@ -20,16 +97,31 @@ impl App {
     pub fn new(channel: Channel) -> Self {
         Self {
             anchor_state: AnchorState::new(channel.clone()),
+            now_playing: NowPlayingBar::new(channel.clone()),
+            toasts: Toasts::new(channel.clone()),
+            jobs: JobsIndicator::new(),
             channel: channel.clone(),
         }
     }
 
     fn apps_iter_mut(&mut self) -> impl Iterator<Item = (&str, Anchor, &mut dyn eframe::App)> {
-        let vec = vec![(
-            "Search",
-            Anchor::Search,
-            &mut self.anchor_state.search as &mut dyn eframe::App,
-        )];
+        let vec = vec![
+            (
+                "Search",
+                Anchor::Search,
+                &mut self.anchor_state.search as &mut dyn eframe::App,
+            ),
+            (
+                "Queue",
+                Anchor::Queue,
+                &mut self.anchor_state.queue as &mut dyn eframe::App,
+            ),
+            (
+                "Settings",
+                Anchor::Settings,
+                &mut self.anchor_state.settings as &mut dyn eframe::App,
+            ),
+        ];
 
         vec.into_iter()
     }
@@ -45,6 +137,9 @@ impl App {
                     selected_anchor = anchor;
                 }
             }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                self.jobs.ui(ui);
+            });
         });
         self.anchor_state.selected_anchor = selected_anchor;
     }
@@ -65,10 +160,60 @@ impl eframe::App for App {
 
         for event in self.channel.rx().try_iter() {
             match event {
-                BackgroundEvent::SearchComplete(results) => {
-                    self.anchor_state.search.set_results(Some(results));
+                BackgroundEvent::SearchComplete { request, results } => {
+                    self.anchor_state.search.set_results(request, Some(results));
+                }
+                BackgroundEvent::SearchMoreComplete { search, results } => {
+                    self.anchor_state.search.append_results(search, results);
+                }
+                BackgroundEvent::SearchSuggestions {
+                    request,
+                    suggestions,
+                } => {
+                    self.anchor_state
+                        .search
+                        .set_suggestions(request, suggestions);
+                }
+                BackgroundEvent::QueueChanged(snapshot) => {
+                    self.now_playing
+                        .set_current(snapshot.current.map(|index| snapshot.items[index].clone()));
+                    self.anchor_state.queue.set_snapshot(snapshot);
+                }
+                BackgroundEvent::PlaybackProgress {
+                    position,
+                    duration,
+                    state,
+                } => {
+                    self.now_playing.set_progress(position, duration, state);
+                }
+                BackgroundEvent::Error {
+                    request,
+                    context,
+                    error,
+                } => {
+                    match context {
+                        ErrorContext::Search(_) => self.anchor_state.search.search_failed(request),
+                        ErrorContext::SearchMore {
+                            search,
+                            ref next_href,
+                        } => {
+                            self.anchor_state
+                                .search
+                                .search_more_failed(search, next_href.clone());
+                        }
+                        _ => {}
+                    }
+                    self.toasts.push(&context, &error);
+                }
+                BackgroundEvent::JobStarted { request, label } => {
+                    self.jobs.started(request, label);
+                }
+                BackgroundEvent::JobProgress { request, progress } => {
+                    self.jobs.progress(request, progress);
+                }
+                BackgroundEvent::JobFinished(request) => {
+                    self.jobs.finished(request);
                 }
-                _ => (),
             }
         }
 
@@ -79,8 +224,12 @@ impl eframe::App for App {
                 self.bar_contents(ui, frame);
             });
 
+        self.now_playing.update(ctx, frame);
+
         egui::CentralPanel::default().show(ctx, |_ui| {
             self.show_selected_app(ctx, frame);
         });
+
+        self.toasts.update(ctx, frame);
     }
 }
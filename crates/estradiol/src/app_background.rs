@@ -1,89 +1,775 @@
 use std::{
-    io::Cursor,
-    num::NonZeroUsize,
-    sync::mpsc::{Receiver, Sender},
+    io::{Cursor, Read, Seek},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, Instant},
 };
 
-use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
-use estradiol_soundcloud::{models::collections::Collection, Client};
-use lru::LruCache;
-use rodio::{Decoder, OutputStream, Sink};
+use estradiol_soundcloud::{
+    cache::{AudioCache, CacheKey},
+    download::Download,
+    models::{
+        collections::Collection, resources::TranscodingPolicy, suggestions::SearchSuggestion,
+    },
+    streaming::StreamOptions,
+    Client,
+};
+use rodio::{Decoder, OutputStream, Sink, Source};
+
+use crate::{
+    app::{Request, SearchQuery, UiEvent},
+    jobs::{JobContext, JobPool, JobSlot, RequestId},
+    presence::{self, PresenceTrack, PresenceUpdate},
+    queue::{Queue, QueueItem, QueueSnapshot},
+    settings::Settings,
+    utils::{self, Downloads},
+};
 
-use crate::app::UiEvent;
+/// Bytes of audio kept on disk before the least recently played tracks are evicted.
+const AUDIO_CACHE_BUDGET: u64 = 1024 * 1024 * 1024;
+/// Most search completions offered at once.
+const SUGGESTIONS: i64 = 8;
+/// How often the sink is checked for the end of the current track.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Whether the background thread logs to the terminal. Off while the
+/// terminal UI owns the screen.
+static LOGGING: AtomicBool = AtomicBool::new(true);
+
+pub fn set_logging(enabled: bool) {
+    LOGGING.store(enabled, Ordering::Relaxed);
+}
+
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if LOGGING.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
 
 #[derive(Debug)]
 pub enum BackgroundEvent {
-    SearchComplete(Collection),
+    SearchComplete {
+        request: RequestId,
+        results: Collection,
+    },
+    SearchSuggestions {
+        request: RequestId,
+        suggestions: Vec<SearchSuggestion>,
+    },
+    /// The page following the results of `search`, to append to them.
+    SearchMoreComplete {
+        search: RequestId,
+        results: Collection,
+    },
+    QueueChanged(QueueSnapshot),
+    PlaybackProgress {
+        position: Duration,
+        duration: Duration,
+        state: PlaybackState,
+    },
+    JobStarted {
+        request: RequestId,
+        label: String,
+    },
+    /// Fraction of the job done, from `0.0` to `1.0`.
+    JobProgress {
+        request: RequestId,
+        progress: f32,
+    },
+    /// The job ended, whether it succeeded, failed or was cancelled.
+    JobFinished(RequestId),
+    Error {
+        request: RequestId,
+        context: ErrorContext,
+        error: BackgroundError,
+    },
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum PlaybackState {
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+/// What the background thread was doing when it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorContext {
+    Search(SearchQuery),
+    SearchMore {
+        search: RequestId,
+        next_href: String,
+    },
+    PlayTrack(QueueItem),
+    Download(QueueItem),
+    /// The download was saved, but without its tags.
+    TagDownload(QueueItem),
+    Seek(Duration),
+    AudioDevice,
+    AudioCache,
+}
+
+impl ErrorContext {
+    /// The request to send again to retry, if retrying can help.
+    pub fn retry(&self) -> Option<UiEvent> {
+        match self {
+            ErrorContext::Search(query) => Some(UiEvent::SearchSubmit(query.clone())),
+            ErrorContext::SearchMore { search, next_href } => Some(UiEvent::SearchMore {
+                search: *search,
+                next_href: next_href.clone(),
+            }),
+            ErrorContext::PlayTrack(item) => Some(UiEvent::PlayTrack(item.clone())),
+            ErrorContext::Download(item) => Some(UiEvent::Download(item.clone())),
+            ErrorContext::Seek(position) => Some(UiEvent::Seek(*position)),
+            ErrorContext::TagDownload(_) | ErrorContext::AudioDevice | ErrorContext::AudioCache => {
+                None
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorContext::Search(query) => write!(f, "Searching for {query}"),
+            ErrorContext::SearchMore { .. } => write!(f, "Loading more results"),
+            ErrorContext::PlayTrack(item) => write!(f, "Playing \"{}\"", item.title),
+            ErrorContext::Download(item) => write!(f, "Downloading \"{}\"", item.title),
+            ErrorContext::TagDownload(item) => write!(f, "Tagging \"{}\"", item.title),
+            ErrorContext::Seek(_) => write!(f, "Seeking"),
+            ErrorContext::AudioDevice => write!(f, "Opening the audio device"),
+            ErrorContext::AudioCache => write!(f, "Caching audio"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BackgroundError {
+    SoundCloud(estradiol_soundcloud::Error),
+    /// The audio device or decoder failed.
+    Audio(String),
+}
+
+impl BackgroundError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            BackgroundError::SoundCloud(err) => err.is_retryable(),
+            BackgroundError::Audio(_) => true,
+        }
+    }
+}
+
+impl From<estradiol_soundcloud::Error> for BackgroundError {
+    fn from(value: estradiol_soundcloud::Error) -> Self {
+        Self::SoundCloud(value)
+    }
+}
+
+impl std::fmt::Display for BackgroundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackgroundError::SoundCloud(err) => write!(f, "{err}"),
+            BackgroundError::Audio(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// The audio device, which only lives as long as its `OutputStream`.
+struct Output {
+    _stream: OutputStream,
+    sink: Sink,
+}
+
+impl Output {
+    fn open() -> Result<Self, BackgroundError> {
+        let (stream, stream_handle) =
+            OutputStream::try_default().map_err(|err| BackgroundError::Audio(err.to_string()))?;
+        let sink =
+            Sink::try_new(&stream_handle).map_err(|err| BackgroundError::Audio(err.to_string()))?;
+        Ok(Self {
+            _stream: stream,
+            sink,
+        })
+    }
+}
+
+/// What the coordinating thread acts on: requests from the UI, and the
+/// results of the jobs it spawned for them.
+enum Command {
+    Request(Request),
+    Loaded {
+        request: RequestId,
+        item: QueueItem,
+        result: Result<LoadedTrack, BackgroundError>,
+    },
+    Disconnected,
+}
+
+/// A track ready to hand to the sink.
+struct LoadedTrack {
+    source: Box<dyn Source<Item = i16> + Send>,
+    /// What the presence shows while it plays, including its duration.
+    presence: PresenceTrack,
+}
+
+/// What jobs need to fetch tracks, shared across the pool.
+#[derive(Clone)]
+struct Loader {
+    client: Arc<Client>,
+    track_cache: Option<Arc<Mutex<AudioCache>>>,
+    ui_event_tx: Sender<BackgroundEvent>,
+    command_tx: Sender<Command>,
+}
+
+fn report(
+    ui_event_tx: &Sender<BackgroundEvent>,
+    request: RequestId,
+    context: ErrorContext,
+    error: BackgroundError,
+) {
+    log!("{context}: {error:?}");
+    let _ = ui_event_tx.send(BackgroundEvent::Error {
+        request,
+        context,
+        error,
+    });
 }
 
 pub fn run_background(
-    background_event_rx: Receiver<UiEvent>,
+    background_event_rx: Receiver<Request>,
     ui_event_tx: Sender<BackgroundEvent>,
-) -> impl Fn() {
+) -> impl FnOnce() {
     move || {
-        let client = Client::new();
-        let mut track_cache: LruCache<i64, Vec<u8>> = LruCache::new(NonZeroUsize::new(4).unwrap());
-        let (_stream, stream_handle) = match OutputStream::try_default() {
-            Ok(stream) => stream,
+        let cache_dir = utils::cache_dir();
+        let client = Arc::new(utils::client());
+        let track_cache = match AudioCache::open(cache_dir.join("audio"), AUDIO_CACHE_BUDGET) {
+            Ok(cache) => Some(Arc::new(Mutex::new(cache))),
             Err(err) => {
-                eprintln!("{err:?}");
-                return;
+                report(
+                    &ui_event_tx,
+                    RequestId::next(),
+                    ErrorContext::AudioCache,
+                    err.into(),
+                );
+                None
             }
         };
-        let sink = match Sink::try_new(&stream_handle) {
-            Ok(sink) => sink,
+        let mut output = match Output::open() {
+            Ok(output) => Some(output),
             Err(err) => {
-                eprintln!("{err:?}");
-                return;
+                report(
+                    &ui_event_tx,
+                    RequestId::next(),
+                    ErrorContext::AudioDevice,
+                    err,
+                );
+                None
             }
         };
 
-        while let Ok(event) = background_event_rx.recv() {
-            match event {
+        let (command_tx, command_rx) = channel::<Command>();
+        {
+            let command_tx = command_tx.clone();
+            std::thread::spawn(move || {
+                for request in background_event_rx {
+                    if command_tx.send(Command::Request(request)).is_err() {
+                        return;
+                    }
+                }
+                let _ = command_tx.send(Command::Disconnected);
+            });
+        }
+        let loader = Loader {
+            client: Arc::clone(&client),
+            track_cache,
+            ui_event_tx: ui_event_tx.clone(),
+            command_tx,
+        };
+        let mut pool = JobPool::new(ui_event_tx.clone());
+        let downloads = Downloads::from_env();
+        let presence_tx = presence::spawn(Settings::open().discord_presence());
+
+        let mut queue = Queue::new();
+        // Duration of the loaded track, so an empty sink means it finished.
+        let mut now_playing: Option<Duration> = None;
+        // The loaded track as the presence shows it.
+        let mut presence_track: Option<PresenceTrack> = None;
+        // The track load whose result gets played; older ones are stale.
+        let mut loading: Option<RequestId> = None;
+        // Tracks that failed to load in a row, so a queue of nothing but
+        // failures isn't skipped through forever.
+        let mut failures: usize = 0;
+
+        let mut polled_at = Instant::now();
+        loop {
+            let received =
+                command_rx.recv_timeout(POLL_INTERVAL.saturating_sub(polled_at.elapsed()));
+            // Polled on time however busy the UI keeps the channel.
+            if polled_at.elapsed() >= POLL_INTERVAL {
+                polled_at = Instant::now();
+                let sink = output.as_ref().map(|output| &output.sink);
+                if let (Some(sink), Some(_), None) = (sink, now_playing, loading) {
+                    if sink.empty() {
+                        now_playing = None;
+                        presence_track = None;
+                        let _ = presence_tx.send(PresenceUpdate::Idle);
+                        if let Some(item) = queue.advance(true) {
+                            let request = RequestId::next();
+                            load(&mut pool, &loader, request, item);
+                            loading = Some(request);
+                        }
+                        let _ = ui_event_tx.send(BackgroundEvent::QueueChanged(queue.snapshot()));
+                    }
+                }
+                let _ = ui_event_tx.send(progress(sink, now_playing));
+            }
+
+            let command = match received {
+                Ok(command) => command,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+
+            let (request, event) = match command {
+                Command::Request(Request { id, event }) => (id, event),
+                Command::Loaded {
+                    request,
+                    item,
+                    result,
+                } => {
+                    if loading != Some(request) {
+                        continue;
+                    }
+                    loading = None;
+                    let Some(Output { sink, .. }) = &output else {
+                        continue;
+                    };
+                    sink.clear();
+                    match result {
+                        Ok(track) => {
+                            failures = 0;
+                            sink.append(track.source);
+                            sink.play();
+                            log!("Playing track");
+                            now_playing = Some(track.presence.duration);
+                            presence_track = Some(track.presence);
+                        }
+                        Err(err) => {
+                            now_playing = None;
+                            presence_track = None;
+                            report(&ui_event_tx, request, ErrorContext::PlayTrack(item), err);
+                            // Skip to the next track, as if this one had played.
+                            failures += 1;
+                            if failures < queue.len() {
+                                if let Some(item) = queue.advance(false) {
+                                    let request = RequestId::next();
+                                    load(&mut pool, &loader, request, item);
+                                    loading = Some(request);
+                                }
+                            }
+                            let _ =
+                                ui_event_tx.send(BackgroundEvent::QueueChanged(queue.snapshot()));
+                        }
+                    }
+                    let _ = presence_tx.send(presence_update(sink, presence_track.as_ref()));
+                    let _ = ui_event_tx.send(progress(Some(sink), now_playing));
+                    continue;
+                }
+                Command::Disconnected => return,
+            };
+
+            let event = match event {
                 UiEvent::SearchSubmit(query) => {
-                    println!("Searching for {query}");
-                    if let Ok(results) = client.search(query.as_str(), 50, 0) {
-                        println!("Obtained {} results", results.collection().len());
-                        let _ = ui_event_tx.send(BackgroundEvent::SearchComplete(results));
+                    search(&mut pool, &loader, request, query);
+                    continue;
+                }
+                UiEvent::SearchSuggest(text) => {
+                    suggest(&mut pool, &loader, request, text);
+                    continue;
+                }
+                UiEvent::SearchMore { search, next_href } => {
+                    search_more(&mut pool, &loader, request, search, next_href);
+                    continue;
+                }
+                UiEvent::Download(item) => {
+                    download(&mut pool, &loader, &downloads, request, item);
+                    continue;
+                }
+                UiEvent::SetDiscordPresence(enabled) => {
+                    let _ = presence_tx.send(PresenceUpdate::SetEnabled(enabled));
+                    continue;
+                }
+                event => event,
+            };
+
+            // Reopen the device on demand, in case it was missing or has come back.
+            if output.is_none() {
+                match Output::open() {
+                    Ok(opened) => output = Some(opened),
+                    Err(err) => {
+                        report(&ui_event_tx, request, ErrorContext::AudioDevice, err);
+                        continue;
                     }
                 }
-                UiEvent::PlayTrack(id) => {
-                    println!("Request to play track {id}");
-                    if let Some(track_bytes) = track_cache.get(&id) {
-                        play_track(track_bytes.clone(), &sink);
+            }
+            let Some(Output { sink, .. }) = &output else {
+                continue;
+            };
+
+            let next = match event {
+                UiEvent::SearchSubmit(_)
+                | UiEvent::SearchSuggest(_)
+                | UiEvent::SearchMore { .. }
+                | UiEvent::Download(_)
+                | UiEvent::SetDiscordPresence(_) => continue,
+                UiEvent::Pause => {
+                    sink.pause();
+                    let _ = presence_tx.send(PresenceUpdate::Idle);
+                    let _ = ui_event_tx.send(progress(Some(sink), now_playing));
+                    continue;
+                }
+                UiEvent::Resume => {
+                    sink.play();
+                    let _ = presence_tx.send(presence_update(sink, presence_track.as_ref()));
+                    let _ = ui_event_tx.send(progress(Some(sink), now_playing));
+                    continue;
+                }
+                UiEvent::Stop => {
+                    pool.cancel(JobSlot::LoadTrack);
+                    loading = None;
+                    sink.clear();
+                    now_playing = None;
+                    presence_track = None;
+                    let _ = presence_tx.send(PresenceUpdate::Idle);
+                    let _ = ui_event_tx.send(progress(Some(sink), now_playing));
+                    continue;
+                }
+                UiEvent::Seek(position) => {
+                    if let Err(err) = sink.try_seek(position) {
+                        report(
+                            &ui_event_tx,
+                            request,
+                            ErrorContext::Seek(position),
+                            BackgroundError::Audio(err.to_string()),
+                        );
+                    }
+                    // Moves the presence's timestamps along with the position.
+                    let _ = presence_tx.send(presence_update(sink, presence_track.as_ref()));
+                    let _ = ui_event_tx.send(progress(Some(sink), now_playing));
+                    continue;
+                }
+                UiEvent::SetVolume(volume) => {
+                    sink.set_volume(volume);
+                    continue;
+                }
+                UiEvent::PlayTrack(item) => Some(queue.play_now(item)),
+                UiEvent::Enqueue(item) => {
+                    queue.enqueue(item);
+                    None
+                }
+                UiEvent::PlayNext(item) => {
+                    queue.play_next(item);
+                    None
+                }
+                UiEvent::QueueRemove(index) => {
+                    if queue.remove(index) {
+                        sink.clear();
+                        now_playing = None;
+                        presence_track = None;
+                        let _ = presence_tx.send(PresenceUpdate::Idle);
+                        queue.current()
                     } else {
-                        let Ok(track) = client.track(id) else {
-                            return;
-                        };
-                        let Some(media) = track.media() else {
-                            return;
-                        };
-                        let Some(transcoding) = media.progressive() else {
-                            return;
-                        };
-                        let Ok(stream) = client.stream(&transcoding) else {
-                            return;
-                        };
-                        let Ok(track_bytes) = client.bytes(&stream.url()) else {
-                            return;
-                        };
-
-                        track_cache.put(id, track_bytes.clone());
-                        play_track(track_bytes, &sink);
+                        None
                     }
                 }
+                UiEvent::QueueMove { from, to } => {
+                    queue.move_item(from, to);
+                    None
+                }
+                UiEvent::QueueJump(index) => queue.jump(index),
+                UiEvent::NextTrack => queue.advance(false),
+                UiEvent::PreviousTrack => queue.previous(),
+                UiEvent::SetShuffle(shuffle) => {
+                    queue.set_shuffle(shuffle);
+                    None
+                }
+                UiEvent::SetRepeat(repeat) => {
+                    queue.set_repeat(repeat);
+                    None
+                }
+            };
+
+            if let Some(item) = next {
+                load(&mut pool, &loader, request, item);
+                loading = Some(request);
             }
+            let _ = ui_event_tx.send(BackgroundEvent::QueueChanged(queue.snapshot()));
+            let _ = ui_event_tx.send(progress(Some(sink), now_playing));
         }
     }
 }
 
-fn play_track(track_bytes: Vec<u8>, sink: &Sink) {
-    let Ok(source) = Decoder::new(Cursor::new(track_bytes)) else {
-        return;
+/// What the presence should show: the track while it plays, else nothing.
+fn presence_update(sink: &Sink, track: Option<&PresenceTrack>) -> PresenceUpdate {
+    match track {
+        Some(track) if !sink.is_paused() => PresenceUpdate::Playing {
+            track: track.clone(),
+            position: sink.get_pos(),
+        },
+        _ => PresenceUpdate::Idle,
+    }
+}
+
+fn progress(sink: Option<&Sink>, now_playing: Option<Duration>) -> BackgroundEvent {
+    let (Some(sink), Some(duration)) = (sink, now_playing) else {
+        return BackgroundEvent::PlaybackProgress {
+            position: Duration::ZERO,
+            duration: Duration::ZERO,
+            state: PlaybackState::Stopped,
+        };
     };
+    BackgroundEvent::PlaybackProgress {
+        position: sink.get_pos(),
+        duration,
+        state: if sink.is_paused() {
+            PlaybackState::Paused
+        } else {
+            PlaybackState::Playing
+        },
+    }
+}
+
+/// Searches for `query`, replacing any search still running.
+fn search(pool: &mut JobPool, loader: &Loader, request: RequestId, query: SearchQuery) {
+    let client = Arc::clone(&loader.client);
+    let ui_event_tx = loader.ui_event_tx.clone();
+    let label = format!("Searching for {query}");
+    pool.spawn(request, Some(JobSlot::Search), label, move |job| {
+        log!("Searching for {query}");
+        let result = client.search_with(&query.text, query.kind, &query.filters, 50, 0);
+        if job.is_cancelled() {
+            return;
+        }
+        match result {
+            Ok(results) => {
+                log!("Obtained {} results", results.collection().len());
+                let _ = ui_event_tx.send(BackgroundEvent::SearchComplete { request, results });
+            }
+            Err(err) => report(
+                &ui_event_tx,
+                request,
+                ErrorContext::Search(query),
+                err.into(),
+            ),
+        }
+    });
+}
+
+/// Fetches completions of `text`, replacing any still on their way. They are
+/// only a convenience, so failures are logged rather than reported.
+fn suggest(pool: &mut JobPool, loader: &Loader, request: RequestId, text: String) {
+    let client = Arc::clone(&loader.client);
+    let ui_event_tx = loader.ui_event_tx.clone();
+    let label = format!("Suggesting searches for {text}");
+    pool.spawn(request, Some(JobSlot::Suggest), label, move |job| {
+        let result = client.search_suggestions(&text, SUGGESTIONS);
+        if job.is_cancelled() {
+            return;
+        }
+        match result {
+            Ok(suggestions) => {
+                let _ = ui_event_tx.send(BackgroundEvent::SearchSuggestions {
+                    request,
+                    suggestions: suggestions.into_collection(),
+                });
+            }
+            Err(err) => log!("Failed to suggest searches for {text}: {err}"),
+        }
+    });
+}
+
+/// Fetches the page at `next_href` of the results of `search`. Shares the
+/// search slot, so a new search cancels it.
+fn search_more(
+    pool: &mut JobPool,
+    loader: &Loader,
+    request: RequestId,
+    search: RequestId,
+    next_href: String,
+) {
+    let client = Arc::clone(&loader.client);
+    let ui_event_tx = loader.ui_event_tx.clone();
+    let label = String::from("Loading more results");
+    pool.spawn(request, Some(JobSlot::Search), label, move |job| {
+        let result = client.next_page(&next_href);
+        if job.is_cancelled() {
+            return;
+        }
+        match result {
+            Ok(results) => {
+                let _ = ui_event_tx.send(BackgroundEvent::SearchMoreComplete { search, results });
+            }
+            Err(err) => report(
+                &ui_event_tx,
+                request,
+                ErrorContext::SearchMore { search, next_href },
+                err.into(),
+            ),
+        }
+    });
+}
+
+/// Loads `item` for playback, replacing any load still running. The result
+/// comes back to the coordinating thread as [`Command::Loaded`].
+fn load(pool: &mut JobPool, loader: &Loader, request: RequestId, item: QueueItem) {
+    let loader = loader.clone();
+    let label = format!("Loading \"{}\"", item.title);
+    pool.spawn(request, Some(JobSlot::LoadTrack), label, move |job| {
+        let Some(result) = loader.load(job, &item).transpose() else {
+            return;
+        };
+        let _ = loader.command_tx.send(Command::Loaded {
+            request,
+            item,
+            result,
+        });
+    });
+}
+
+/// Saves `item` to the download directory, alongside any other downloads.
+fn download(
+    pool: &mut JobPool,
+    loader: &Loader,
+    downloads: &Downloads,
+    request: RequestId,
+    item: QueueItem,
+) {
+    let client = Arc::clone(&loader.client);
+    let ui_event_tx = loader.ui_event_tx.clone();
+    let downloads = downloads.clone();
+    let label = format!("Downloading \"{}\"", item.title);
+    pool.spawn(request, None, label, move |job| {
+        let downloaded = client.track(item.id).and_then(|track| {
+            client.download_with(
+                &track,
+                &downloads.dir,
+                &TranscodingPolicy::default(),
+                &downloads.template,
+                |progress| {
+                    if let Some(fraction) = progress.fraction() {
+                        job.progress(fraction);
+                    }
+                },
+            )
+        });
+        match downloaded {
+            Ok(Download { path, tag_error }) => {
+                log!("Downloaded {}", path.display());
+                if let Some(err) = tag_error {
+                    report(
+                        &ui_event_tx,
+                        request,
+                        ErrorContext::TagDownload(item),
+                        err.into(),
+                    );
+                }
+            }
+            Err(err) => report(
+                &ui_event_tx,
+                request,
+                ErrorContext::Download(item),
+                err.into(),
+            ),
+        }
+    });
+}
+
+impl Loader {
+    /// Opens a decoder on `item`, from the cache if it is there so cached
+    /// tracks play offline, or returns `None` if the job is cancelled first.
+    fn load(
+        &self,
+        job: &JobContext,
+        item: &QueueItem,
+    ) -> Result<Option<LoadedTrack>, BackgroundError> {
+        log!("Request to play track {}", item.id);
+        let presence = PresenceTrack::from(item);
+        let cached = self
+            .track_cache
+            .as_deref()
+            .and_then(|cache| lock(cache).get_track(item.id));
+        if let Some(track_bytes) = cached {
+            match decode(Cursor::new(track_bytes), presence.clone()) {
+                Ok(track) => return Ok(Some(track)),
+                // Cached before playback stuck to mp3; fetch a playable copy.
+                Err(err) => log!("Cached track {} doesn't decode: {err}", item.id),
+            }
+        }
+
+        let track = self.client.track(item.id)?;
+        let transcoding = track.transcoding(&TranscodingPolicy::mp3())?;
+        let key = CacheKey::new(item.id, &transcoding.preset());
+        let stream = self.client.stream(&transcoding)?;
+        if !transcoding.is_hls() {
+            // Progressive files are streamed, so playback starts after the first chunk.
+            let reader = self
+                .client
+                .stream_reader(&stream.url(), StreamOptions::default())?;
+            if let Some(track_cache) = &self.track_cache {
+                let track_cache = Arc::clone(track_cache);
+                let ui_event_tx = self.ui_event_tx.clone();
+                let request = job.request();
+                reader.record(move |track_bytes| {
+                    if let Err(err) = lock(&track_cache).put(key, &track_bytes) {
+                        report(&ui_event_tx, request, ErrorContext::AudioCache, err.into());
+                    }
+                });
+            }
+            return decode(reader, presence).map(Some);
+        }
+
+        let urls = self.client.hls_playlist(&stream)?.urls();
+        let mut track_bytes = Vec::new();
+        for (index, url) in urls.iter().enumerate() {
+            if job.is_cancelled() {
+                return Ok(None);
+            }
+            track_bytes.extend(self.client.bytes(url)?);
+            job.progress((index + 1) as f32 / urls.len() as f32);
+        }
+        if let Some(track_cache) = &self.track_cache {
+            if let Err(err) = lock(track_cache).put(key, &track_bytes) {
+                report(
+                    &self.ui_event_tx,
+                    job.request(),
+                    ErrorContext::AudioCache,
+                    err.into(),
+                );
+            }
+        }
+        decode(Cursor::new(track_bytes), presence).map(Some)
+    }
+}
+
+/// Locks the audio cache, even if a job panicked while holding it: the cache
+/// checks its entries on read, so a half-done write can't hurt.
+fn lock(track_cache: &Mutex<AudioCache>) -> MutexGuard<'_, AudioCache> {
+    track_cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-    sink.clear();
-    sink.append(source);
-    sink.play();
-    println!("Playing track");
+fn decode<R>(reader: R, presence: PresenceTrack) -> Result<LoadedTrack, BackgroundError>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    let source = Decoder::new(reader).map_err(|err| BackgroundError::Audio(err.to_string()))?;
+    Ok(LoadedTrack {
+        source: Box::new(source),
+        presence,
+    })
 }
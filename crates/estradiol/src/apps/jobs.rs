@@ -0,0 +1,73 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::jobs::RequestId;
+
+/// How often to redraw while jobs run, so their progress keeps moving.
+const REPAINT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug)]
+struct Job {
+    label: String,
+    /// Fraction done, or `None` until the job can tell.
+    progress: Option<f32>,
+}
+
+/// Background jobs in flight, with their progress, shown in the top bar.
+#[derive(Debug, Default)]
+pub struct JobsIndicator {
+    jobs: BTreeMap<RequestId, Job>,
+}
+
+impl JobsIndicator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn started(&mut self, request: RequestId, label: String) {
+        self.jobs.insert(
+            request,
+            Job {
+                label,
+                progress: None,
+            },
+        );
+    }
+
+    pub fn progress(&mut self, request: RequestId, progress: f32) {
+        if let Some(job) = self.jobs.get_mut(&request) {
+            job.progress = Some(progress);
+        }
+    }
+
+    pub fn finished(&mut self, request: RequestId) {
+        self.jobs.remove(&request);
+    }
+
+    /// Labels and progress of the jobs in flight, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<f32>)> {
+        self.jobs
+            .values()
+            .map(|job| (job.label.as_str(), job.progress))
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui) {
+        if !self.jobs.is_empty() {
+            ui.ctx().request_repaint_after(REPAINT_INTERVAL);
+        }
+        for job in self.jobs.values().rev() {
+            match job.progress {
+                Some(progress) => {
+                    ui.add(
+                        egui::ProgressBar::new(progress)
+                            .desired_width(160.0)
+                            .text(&job.label),
+                    );
+                }
+                None => {
+                    ui.label(&job.label);
+                    ui.spinner();
+                }
+            }
+        }
+    }
+}
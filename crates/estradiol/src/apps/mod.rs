@@ -1 +1,6 @@
+pub mod jobs;
+pub mod now_playing;
+pub mod queue;
 pub mod search;
+pub mod settings;
+pub mod toasts;
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use crate::{app::UiEvent, app_background::PlaybackState, queue::QueueItem, utils::Channel};
+
+/// How often to redraw while playing, so the position keeps moving.
+const REPAINT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug)]
+pub struct NowPlayingBar {
+    current: Option<QueueItem>,
+    position: Duration,
+    duration: Duration,
+    state: PlaybackState,
+    volume: f32,
+    /// Position under the seek slider while it is being dragged.
+    seeking: Option<f32>,
+    channel: Channel,
+}
+
+impl NowPlayingBar {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            current: None,
+            position: Duration::ZERO,
+            duration: Duration::ZERO,
+            state: PlaybackState::Stopped,
+            volume: 1.0,
+            seeking: None,
+            channel,
+        }
+    }
+
+    pub fn set_current(&mut self, current: Option<QueueItem>) {
+        self.current = current;
+    }
+
+    pub fn set_progress(&mut self, position: Duration, duration: Duration, state: PlaybackState) {
+        self.position = position;
+        self.duration = duration;
+        self.state = state;
+    }
+
+    fn send(&self, event: UiEvent) {
+        self.channel.send(event);
+    }
+}
+
+impl eframe::App for NowPlayingBar {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.state == PlaybackState::Playing {
+            ctx.request_repaint_after(REPAINT_INTERVAL);
+        }
+
+        egui::TopBottomPanel::bottom("now_playing_bar")
+            .frame(egui::Frame::none().inner_margin(4.0))
+            .show(ctx, |ui| {
+                if let Some(current) = &self.current {
+                    ui.label(format!("{} — {}", current.title, current.username));
+                }
+                ui.horizontal(|ui| {
+                    let stopped = self.state == PlaybackState::Stopped;
+                    let (label, event) = if self.state == PlaybackState::Playing {
+                        ("pause", UiEvent::Pause)
+                    } else {
+                        ("resume", UiEvent::Resume)
+                    };
+                    if ui.add_enabled(!stopped, egui::Button::new(label)).clicked() {
+                        self.send(event);
+                    }
+                    if ui
+                        .add_enabled(!stopped, egui::Button::new("stop"))
+                        .clicked()
+                    {
+                        self.send(UiEvent::Stop);
+                    }
+
+                    let mut volume = self.volume;
+                    ui.label("volume");
+                    if ui
+                        .add(egui::Slider::new(&mut volume, 0.0..=1.0).show_value(false))
+                        .changed()
+                    {
+                        self.volume = volume;
+                        self.send(UiEvent::SetVolume(volume));
+                    }
+
+                    let duration = self.duration.as_secs_f32();
+                    let mut position = self
+                        .seeking
+                        .unwrap_or_else(|| self.position.as_secs_f32().min(duration));
+                    ui.label(format_time(position));
+                    ui.spacing_mut().slider_width = (ui.available_width() - 48.0).max(0.0);
+                    let slider = ui.add_enabled(
+                        !stopped,
+                        egui::Slider::new(&mut position, 0.0..=duration).show_value(false),
+                    );
+                    if slider.dragged() || slider.changed() {
+                        self.seeking = Some(position);
+                    }
+                    if slider.drag_stopped() || (slider.changed() && !slider.dragged()) {
+                        self.seeking = None;
+                        self.send(UiEvent::Seek(Duration::from_secs_f32(position)));
+                    }
+                    ui.label(format_time(duration));
+                });
+            });
+    }
+}
+
+fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
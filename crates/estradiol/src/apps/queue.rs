@@ -0,0 +1,95 @@
+use crate::{
+    app::UiEvent,
+    queue::{QueueSnapshot, RepeatMode},
+    utils::Channel,
+};
+
+#[derive(Debug)]
+pub struct QueueApp {
+    snapshot: QueueSnapshot,
+    channel: Channel,
+}
+
+impl QueueApp {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            snapshot: QueueSnapshot::default(),
+            channel,
+        }
+    }
+
+    pub fn set_snapshot(&mut self, snapshot: QueueSnapshot) {
+        self.snapshot = snapshot;
+    }
+
+    fn send(&self, event: UiEvent) {
+        self.channel.send(event);
+    }
+}
+
+impl eframe::App for QueueApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("previous").clicked() {
+                    self.send(UiEvent::PreviousTrack);
+                }
+                if ui.button("next").clicked() {
+                    self.send(UiEvent::NextTrack);
+                }
+                let mut shuffle = self.snapshot.shuffle;
+                if ui.checkbox(&mut shuffle, "Shuffle").changed() {
+                    self.send(UiEvent::SetShuffle(shuffle));
+                }
+                let mut repeat = self.snapshot.repeat;
+                egui::ComboBox::from_id_salt("queue_repeat")
+                    .selected_text(repeat.to_string())
+                    .show_ui(ui, |ui| {
+                        for mode in [RepeatMode::Off, RepeatMode::One, RepeatMode::All] {
+                            ui.selectable_value(&mut repeat, mode, mode.to_string());
+                        }
+                    });
+                if repeat != self.snapshot.repeat {
+                    self.send(UiEvent::SetRepeat(repeat));
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().animated(true).show(ui, |ui| {
+                let len = self.snapshot.items.len();
+                for (index, item) in self.snapshot.items.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(index > 0, egui::Button::new("↑")).clicked() {
+                            self.send(UiEvent::QueueMove {
+                                from: index,
+                                to: index - 1,
+                            });
+                        }
+                        if ui
+                            .add_enabled(index + 1 < len, egui::Button::new("↓"))
+                            .clicked()
+                        {
+                            self.send(UiEvent::QueueMove {
+                                from: index,
+                                to: index + 1,
+                            });
+                        }
+                        if ui.button("✖").clicked() {
+                            self.send(UiEvent::QueueRemove(index));
+                        }
+                        let current = self.snapshot.current == Some(index);
+                        if ui
+                            .selectable_label(
+                                current,
+                                format!("{} — {}", item.title, item.username),
+                            )
+                            .double_clicked()
+                        {
+                            self.send(UiEvent::QueueJump(index));
+                        }
+                    });
+                }
+            });
+        });
+    }
+}
@@ -1,15 +1,45 @@
+use std::time::{Duration, Instant};
+
+use egui::PopupCloseBehavior;
 use egui_extras::{Size, StripBuilder};
-use estradiol_soundcloud::models::{
-    collections::Collection,
-    resources::{Resource, ResourceKind},
+use estradiol_soundcloud::{
+    models::{collections::Collection, resources::Resource, suggestions::SearchSuggestion},
+    search::{CreatedAtFilter, DurationFilter, LicenseFilter, SearchKind},
+};
+
+use crate::{
+    app::{SearchQuery, UiEvent},
+    history::{self, SearchHistory},
+    jobs::RequestId,
+    queue::QueueItem,
+    utils::Channel,
 };
 
-use crate::{app::UiEvent, utils::Channel};
+/// How close to the bottom of the results, in points, the next page is fetched.
+const LOAD_MORE_MARGIN: f32 = 200.0;
+/// How long typing has to pause before the query is searched for.
+const DEBOUNCE: Duration = Duration::from_millis(400);
 
 #[derive(Debug)]
 pub struct SearchApp {
-    search: String,
+    search: SearchQuery,
     results: Option<Collection>,
+    /// The newest search sent or answered; answers to older ones are stale.
+    latest: Option<RequestId>,
+    searching: bool,
+    /// Whether the page after the current results was asked for.
+    loading_more: bool,
+    /// The page that last failed to load, which scrolling doesn't ask for
+    /// again; the error's retry or a new search does.
+    more_failed: Option<String>,
+    /// When the query was last typed in, until it is searched for.
+    edited: Option<Instant>,
+    suggestions: Vec<SearchSuggestion>,
+    /// The newest suggestions asked for; any others are stale.
+    latest_suggest: Option<RequestId>,
+    /// Whether suggestions arrived that the dropdown hasn't opened for.
+    show_suggestions: bool,
+    history: SearchHistory,
     selected_resource: Option<Resource>,
     channel: Channel,
 }
@@ -17,63 +47,438 @@ pub struct SearchApp {
 impl SearchApp {
     pub fn new(channel: Channel) -> Self {
         Self {
-            search: String::from(""),
+            search: SearchQuery::default(),
             results: None,
+            latest: None,
+            searching: false,
+            loading_more: false,
+            more_failed: None,
+            edited: None,
+            suggestions: Vec::new(),
+            latest_suggest: None,
+            show_suggestions: false,
+            history: SearchHistory::open(),
             selected_resource: None,
             channel,
         }
     }
 
-    pub fn set_results(&mut self, results: Option<Collection>) {
-        self.results = results;
+    /// Shows `results` unless a newer search was sent since `request`.
+    pub fn set_results(&mut self, request: RequestId, results: Option<Collection>) {
+        if self.answer(request) {
+            self.results = results;
+        }
+    }
+
+    pub fn search_failed(&mut self, request: RequestId) {
+        self.answer(request);
+    }
+
+    /// Offers `suggestions` unless newer ones were asked for since `request`,
+    /// or the query was submitted.
+    pub fn set_suggestions(&mut self, request: RequestId, suggestions: Vec<SearchSuggestion>) {
+        if self.latest_suggest == Some(request) {
+            self.show_suggestions = !suggestions.is_empty();
+            self.suggestions = suggestions;
+        }
+    }
+
+    /// Appends the page after the results of `search`, if they are still shown.
+    pub fn append_results(&mut self, search: RequestId, page: Collection) {
+        if self.latest != Some(search) {
+            return;
+        }
+        if let Some(results) = &mut self.results {
+            results.append(page);
+        }
+        self.loading_more = false;
+        self.more_failed = None;
+    }
+
+    pub fn search_more_failed(&mut self, search: RequestId, next_href: String) {
+        if self.latest == Some(search) {
+            self.loading_more = false;
+            self.more_failed = Some(next_href);
+        }
+    }
+
+    /// Records an answer to `request`, returning whether it is still current.
+    fn answer(&mut self, request: RequestId) -> bool {
+        if self.latest.is_some_and(|latest| request < latest) {
+            return false;
+        }
+        self.latest = Some(request);
+        self.searching = false;
+        self.loading_more = false;
+        true
+    }
+
+    /// Searches for the query now, dropping any suggestions for it.
+    fn submit(&mut self) {
+        self.edited = None;
+        self.latest_suggest = None;
+        self.suggestions.clear();
+        self.more_failed = None;
+        self.latest = Some(
+            self.channel
+                .send(UiEvent::SearchSubmit(self.search.clone())),
+        );
+        self.searching = true;
+    }
+
+    /// Searches for the query as the user asked to, remembering it.
+    fn run(&mut self) {
+        self.history.record(&self.search);
+        self.submit();
+    }
+
+    /// Searches for the query once typing pauses, and asks for completions
+    /// of it to offer while the user carries on.
+    fn debounce(&mut self, ctx: &egui::Context) {
+        let Some(edited) = self.edited else {
+            return;
+        };
+        let elapsed = edited.elapsed();
+        if elapsed < DEBOUNCE {
+            ctx.request_repaint_after(DEBOUNCE - elapsed);
+            return;
+        }
+        if self.search.text.trim().is_empty() {
+            self.edited = None;
+            self.latest_suggest = None;
+            self.suggestions.clear();
+            return;
+        }
+        self.submit();
+        self.latest_suggest = Some(
+            self.channel
+                .send(UiEvent::SearchSuggest(self.search.text.clone())),
+        );
+    }
+
+    /// Shows the suggestions in a dropdown below the query; picking one
+    /// searches for it.
+    fn suggestions_ui(&mut self, ui: &mut egui::Ui, query: &egui::Response) {
+        let popup_id = ui.make_persistent_id("search_suggestions");
+        if self.suggestions.is_empty() {
+            if ui.memory(|memory| memory.is_popup_open(popup_id)) {
+                ui.memory_mut(egui::Memory::close_popup);
+            }
+            return;
+        }
+        if std::mem::take(&mut self.show_suggestions) {
+            ui.memory_mut(|memory| memory.open_popup(popup_id));
+        }
+        let picked = egui::popup_below_widget(
+            ui,
+            popup_id,
+            query,
+            PopupCloseBehavior::CloseOnClick,
+            |ui| {
+                let mut picked = None;
+                for suggestion in &self.suggestions {
+                    if ui.selectable_label(false, suggestion.output()).clicked() {
+                        picked = Some(suggestion.query());
+                    }
+                }
+                picked
+            },
+        )
+        .flatten();
+        if let Some(query) = picked {
+            self.search.text = query;
+            self.run();
+        }
+    }
+
+    /// Asks for the page after the current results, unless one is on its
+    /// way or it failed to load.
+    fn load_more(&mut self) {
+        if self.searching || self.loading_more {
+            return;
+        }
+        let (Some(search), Some(next_href)) = (
+            self.latest,
+            self.results.as_ref().and_then(Collection::next_href),
+        ) else {
+            return;
+        };
+        if self.more_failed.as_ref() == Some(&next_href) {
+            return;
+        }
+        self.channel.send(UiEvent::SearchMore { search, next_href });
+        self.loading_more = true;
+    }
+
+    /// The saved searches and the history, each run again by clicking it.
+    fn history_ui(&mut self, ui: &mut egui::Ui) {
+        let mut run = None;
+        ui.horizontal(|ui| {
+            ui.heading("Saved");
+            let saved = self.history.is_saved(&self.search);
+            if ui
+                .add_enabled(!saved, egui::Button::new("save current"))
+                .clicked()
+            {
+                self.history.pin(&self.search);
+            }
+        });
+        let mut unpin = None;
+        for (index, query) in self.history.saved().iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✕").on_hover_text("delete").clicked() {
+                    unpin = Some(index);
+                }
+                if ui.link(query.to_string()).clicked() {
+                    run = Some(query.clone());
+                }
+            });
+        }
+        if let Some(index) = unpin {
+            self.history.unpin(index);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.heading("History");
+            if ui.button("clear").clicked() {
+                self.history.clear();
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut limit = self.history.limit();
+            ui.label("keep last");
+            if ui
+                .add(egui::DragValue::new(&mut limit).range(1..=500))
+                .changed()
+            {
+                self.history.set_limit(limit);
+            }
+            ui.label("searches");
+        });
+        let mut remove = None;
+        let mut pin = None;
+        egui::ScrollArea::vertical()
+            .id_salt("search_history")
+            .show(ui, |ui| {
+                for (index, entry) in self.history.entries().iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("✕").on_hover_text("delete").clicked() {
+                            remove = Some(index);
+                        }
+                        if ui.small_button("📌").on_hover_text("save").clicked() {
+                            pin = Some(entry.query.clone());
+                        }
+                        if ui.link(entry.query.to_string()).clicked() {
+                            run = Some(entry.query.clone());
+                        }
+                        ui.weak(history::ago(entry.searched_at));
+                    });
+                }
+            });
+        if let Some(index) = remove {
+            self.history.remove(index);
+        }
+        if let Some(query) = pin {
+            self.history.pin(&query);
+        }
+        if let Some(query) = run {
+            self.search = query;
+            self.run();
+        }
+    }
+
+    fn filters_ui(&mut self, ui: &mut egui::Ui) {
+        let filters = &mut self.search.filters;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("search_kind")
+                .selected_text(self.search.kind.to_string())
+                .show_ui(ui, |ui| {
+                    for kind in SearchKind::ALL {
+                        ui.selectable_value(&mut self.search.kind, kind, kind.to_string());
+                    }
+                });
+            filter_combo(ui, "Length", &mut filters.duration, DurationFilter::ALL);
+            filter_combo(
+                ui,
+                "Uploaded",
+                &mut filters.created_at,
+                CreatedAtFilter::ALL,
+            );
+            filter_combo(ui, "License", &mut filters.license, LicenseFilter::ALL);
+            let genre = filters.genre.get_or_insert_with(String::new);
+            ui.add(
+                egui::TextEdit::singleline(genre)
+                    .hint_text("genre or tag")
+                    .desired_width(120.0),
+            );
+        });
+    }
+
+    /// One row per result, in the style of its kind. Returns the clicked one.
+    fn result_ui(ui: &mut egui::Ui, resource: &Resource, selected: bool) -> bool {
+        let user = resource
+            .user()
+            .and_then(|user| user.username())
+            .unwrap_or_default();
+        let text = match resource {
+            Resource::Track(_) => {
+                format!("🎵 {} — {user}", resource.title().unwrap_or_default())
+            }
+            Resource::User(user) => {
+                let mut text = format!("👤 {}", user.username().unwrap_or_default());
+                if let Some(followers) = user.followers_count() {
+                    text.push_str(&format!(" · {followers} followers"));
+                }
+                text
+            }
+            Resource::Playlist(playlist) => {
+                let icon = if playlist.is_album() { "💿" } else { "📃" };
+                let mut text = format!("{icon} {} — {user}", resource.title().unwrap_or_default());
+                if let Some(track_count) = playlist.track_count() {
+                    text.push_str(&format!(" · {track_count} tracks"));
+                }
+                text
+            }
+        };
+        ui.selectable_label(selected, text).clicked()
+    }
+
+    fn details_ui(&mut self, ui: &mut egui::Ui, resource: &Resource) {
+        match resource {
+            Resource::Track(_) => {
+                ui.label(resource.title().unwrap_or_default());
+                if let Some(user) = resource.user() {
+                    ui.label(user.username().unwrap_or_default());
+                }
+                let item = QueueItem::from(resource);
+                ui.horizontal(|ui| {
+                    if ui.button("play").clicked() {
+                        self.channel.send(UiEvent::PlayTrack(item.clone()));
+                    }
+                    if ui.button("play next").clicked() {
+                        self.channel.send(UiEvent::PlayNext(item.clone()));
+                    }
+                    if ui.button("enqueue").clicked() {
+                        self.channel.send(UiEvent::Enqueue(item.clone()));
+                    }
+                    if ui.button("download").clicked() {
+                        self.channel.send(UiEvent::Download(item));
+                    }
+                });
+            }
+            Resource::User(user) => {
+                ui.label(user.username().unwrap_or_default());
+                if let Some(followers) = user.followers_count() {
+                    ui.label(format!("{followers} followers"));
+                }
+            }
+            Resource::Playlist(playlist) => {
+                ui.label(resource.title().unwrap_or_default());
+                if let Some(user) = resource.user() {
+                    ui.label(user.username().unwrap_or_default());
+                }
+                let kind = if playlist.is_album() {
+                    "Album"
+                } else {
+                    "Playlist"
+                };
+                match playlist.track_count() {
+                    Some(track_count) => ui.label(format!("{kind} · {track_count} tracks")),
+                    None => ui.label(kind),
+                };
+            }
+        }
+        if let Some(permalink_url) = resource.permalink_url() {
+            ui.hyperlink_to("open on SoundCloud", permalink_url);
+        }
     }
 }
 
+/// A combo box choosing one of `options`, or none for no filter.
+fn filter_combo<T>(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<T>,
+    options: impl IntoIterator<Item = T>,
+) where
+    T: Copy + PartialEq + std::fmt::Display,
+{
+    let selected = value.map_or_else(|| format!("{label}: any"), |value| value.to_string());
+    egui::ComboBox::from_id_salt(label)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, "Any");
+            for option in options {
+                ui.selectable_value(value, Some(option), option.to_string());
+            }
+        });
+}
+
 impl eframe::App for SearchApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::right("search_history")
+            .default_width(220.0)
+            .show(ctx, |ui| self.history_ui(ui));
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.text_edit_singleline(&mut self.search);
-                if ui.button("search").clicked() {
-                    let _ = self
-                        .channel
-                        .tx()
-                        .send(UiEvent::SearchSubmit(self.search.clone()));
-                }
-            });
+            let query = ui
+                .horizontal(|ui| {
+                    let response = ui.text_edit_singleline(&mut self.search.text);
+                    if response.changed() {
+                        self.edited = Some(Instant::now());
+                    }
+                    let entered = response.lost_focus()
+                        && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                    if ui.button("search").clicked() || entered {
+                        self.run();
+                    }
+                    if self.searching {
+                        ui.spinner();
+                    }
+                    response
+                })
+                .inner;
+            self.suggestions_ui(ui, &query);
+            self.debounce(ctx);
+            self.filters_ui(ui);
+            if let Some(results) = &self.results {
+                let count = match results.total_results() {
+                    Some(total) => format!("{} of {total} results", results.len()),
+                    None => format!("{} results", results.len()),
+                };
+                ui.label(count);
+            }
             StripBuilder::new(ui)
                 .size(Size::remainder())
                 .size(Size::initial(160.0))
                 .vertical(|mut strip| {
                     strip.cell(|ui| {
-                        egui::ScrollArea::vertical().animated(true).show(ui, |ui| {
+                        let output = egui::ScrollArea::vertical().animated(true).show(ui, |ui| {
                             let Some(results) = &self.results else {
                                 return;
                             };
                             let mut selected_resource = self.selected_resource.clone();
                             for resource in results.collection().into_iter() {
-                                let ResourceKind::Track = resource.kind() else {
-                                    continue;
-                                };
-                                let selected = {
-                                    if let Some(selected_resource) = selected_resource.clone() {
-                                        selected_resource.id() == resource.id()
-                                    } else {
-                                        false
-                                    }
-                                };
-                                if ui
-                                    .selectable_label(
-                                        selected,
-                                        resource.title().unwrap_or_default(),
-                                    )
-                                    .clicked()
-                                {
+                                let selected = selected_resource.as_ref().is_some_and(|selected| {
+                                    std::mem::discriminant(selected)
+                                        == std::mem::discriminant(&resource)
+                                        && selected.id() == resource.id()
+                                });
+                                if Self::result_ui(ui, &resource, selected) {
                                     selected_resource = Some(resource);
                                 }
                             }
                             self.selected_resource = selected_resource;
+                            if self.loading_more {
+                                ui.spinner();
+                            }
                         });
+                        // Appending only grows the content below, so the
+                        // offset and what is in view stay put.
+                        let bottom = output.state.offset.y + output.inner_rect.height();
+                        if bottom >= output.content_size.y - LOAD_MORE_MARGIN {
+                            self.load_more();
+                        }
                     });
                     strip.cell(|ui| {
                         ui.separator();
@@ -86,7 +491,10 @@ impl eframe::App for SearchApp {
                                     else {
                                         return;
                                     };
-                                    let Some(artwork_url) = selected_resource.artwork_url() else {
+                                    let artwork_url = selected_resource
+                                        .artwork_url()
+                                        .or_else(|| selected_resource.avatar_url());
+                                    let Some(artwork_url) = artwork_url else {
                                         return;
                                     };
                                     ui.add(
@@ -99,16 +507,7 @@ impl eframe::App for SearchApp {
                                     else {
                                         return;
                                     };
-                                    ui.label(selected_resource.title().unwrap_or_default());
-                                    if let Some(user) = selected_resource.user() {
-                                        ui.label(user.username().unwrap_or_default());
-                                    }
-                                    if ui.button("play").clicked() {
-                                        let _ = self
-                                            .channel
-                                            .tx()
-                                            .send(UiEvent::PlayTrack(selected_resource.id()));
-                                    }
+                                    self.details_ui(ui, &selected_resource);
                                 });
                             });
                     });
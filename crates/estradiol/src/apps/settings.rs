@@ -0,0 +1,42 @@
+use crate::{app::UiEvent, presence, settings::Settings, utils::Channel};
+
+#[derive(Debug)]
+pub struct SettingsApp {
+    settings: Settings,
+    channel: Channel,
+}
+
+impl SettingsApp {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            settings: Settings::open(),
+            channel,
+        }
+    }
+}
+
+impl eframe::App for SettingsApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let available = presence::is_available();
+            let mut discord_presence = self.settings.discord_presence();
+            if ui
+                .add_enabled(
+                    available,
+                    egui::Checkbox::new(&mut discord_presence, "Show the playing track on Discord"),
+                )
+                .changed()
+            {
+                self.settings.set_discord_presence(discord_presence);
+                self.channel
+                    .send(UiEvent::SetDiscordPresence(discord_presence));
+            }
+            if !available {
+                ui.weak(
+                    "Unavailable: set ESTRADIOL_DISCORD_CLIENT_ID to the id of \
+                     a Discord application to show it under.",
+                );
+            }
+        });
+    }
+}
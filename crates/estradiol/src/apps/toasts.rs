@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    app::UiEvent,
+    app_background::{BackgroundError, ErrorContext},
+    utils::Channel,
+};
+
+/// How long a toast stays up unless the pointer is over it.
+const TOAST_LIFETIME: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+struct Toast {
+    title: String,
+    message: String,
+    retry: Option<UiEvent>,
+    shown_at: Instant,
+}
+
+/// Failures reported by the background thread, stacked in the corner.
+#[derive(Debug)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    channel: Channel,
+}
+
+impl Toasts {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            toasts: Vec::new(),
+            channel,
+        }
+    }
+
+    pub fn push(&mut self, context: &ErrorContext, error: &BackgroundError) {
+        self.toasts.push(Toast {
+            title: format!("{context} failed"),
+            message: error.to_string(),
+            retry: context.retry().filter(|_| error.is_retryable()),
+            shown_at: Instant::now(),
+        });
+    }
+}
+
+impl eframe::App for Toasts {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.toasts.is_empty() {
+            return;
+        }
+        ctx.request_repaint_after(Duration::from_secs(1));
+
+        let mut dismissed = Vec::new();
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -64.0))
+            .show(ctx, |ui| {
+                for (index, toast) in self.toasts.iter_mut().enumerate() {
+                    let response = egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_max_width(320.0);
+                        ui.strong(&toast.title);
+                        ui.label(&toast.message);
+                        ui.horizontal(|ui| {
+                            if let Some(retry) = &toast.retry {
+                                if ui.button("retry").clicked() {
+                                    self.channel.send(retry.clone());
+                                    dismissed.push(index);
+                                }
+                            }
+                            if ui.button("dismiss").clicked() {
+                                dismissed.push(index);
+                            }
+                        });
+                    });
+                    if response.response.contains_pointer() {
+                        toast.shown_at = Instant::now();
+                    } else if toast.shown_at.elapsed() > TOAST_LIFETIME {
+                        dismissed.push(index);
+                    }
+                }
+            });
+
+        for index in dismissed.into_iter().rev() {
+            self.toasts.remove(index);
+        }
+    }
+}
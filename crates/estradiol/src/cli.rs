@@ -0,0 +1,518 @@
+use std::{fmt::Display, io::Cursor, path::PathBuf, process::ExitCode, time::Duration};
+
+use clap::{Parser, Subcommand};
+use estradiol_soundcloud::{
+    download::FilenameTemplate,
+    models::{
+        playlists::Playlist,
+        resources::{Resource, TranscodingPolicy},
+        tracks::Track,
+        users::User,
+    },
+    streaming::StreamOptions,
+    Client,
+};
+use rodio::{Decoder, OutputStream, Sink};
+use serde::Serialize;
+
+use crate::utils::{self, Downloads};
+
+/// SoundCloud player. Runs the GUI unless given a subcommand.
+#[derive(Debug, Parser)]
+#[command(name = "estradiol", version)]
+pub struct Cli {
+    /// Print results as JSON, for scripts.
+    #[arg(long, global = true)]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Search for tracks, users and playlists.
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// Show a track, user or playlist, by id (tracks only) or URL.
+    Info { target: String },
+    /// Play a track on the default audio device.
+    Play { target: String },
+    /// Download tracks, or every track of a playlist, tagged.
+    Download {
+        #[arg(required = true)]
+        targets: Vec<String>,
+        /// Directory to save into, instead of `ESTRADIOL_DOWNLOAD_DIR`.
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// File name template, instead of `ESTRADIOL_FILENAME_TEMPLATE`.
+        #[arg(long)]
+        template: Option<String>,
+    },
+    /// List a playlist's tracks.
+    Playlist { target: String },
+    /// Show a user and their tracks.
+    User {
+        target: String,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// Play from the terminal, with keyboard controls.
+    Tui,
+}
+
+#[derive(Debug)]
+enum CliError {
+    SoundCloud(estradiol_soundcloud::Error),
+    /// The audio device or decoder failed.
+    Audio(String),
+    /// The target resolved to the wrong kind of resource.
+    WrongKind {
+        target: String,
+        expected: &'static str,
+    },
+}
+
+impl From<estradiol_soundcloud::Error> for CliError {
+    fn from(value: estradiol_soundcloud::Error) -> Self {
+        Self::SoundCloud(value)
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SoundCloud(err) => write!(f, "{err}"),
+            Self::Audio(err) => write!(f, "audio error: {err}"),
+            Self::WrongKind { target, expected } => write!(f, "{target} is not a {expected}"),
+        }
+    }
+}
+
+/// Runs `command` to completion, printing its results to stdout and any
+/// error to stderr.
+pub fn run(command: Command, json: bool) -> ExitCode {
+    let client = utils::client();
+    let result = match command {
+        Command::Search { query, limit } => search(&client, &query, limit, json),
+        Command::Info { target } => info(&client, &target, json),
+        Command::Play { target } => play(&client, &target, json),
+        Command::Download {
+            targets,
+            dir,
+            template,
+        } => download(&client, &targets, dir, template, json),
+        Command::Playlist { target } => playlist(&client, &target, json),
+        Command::User { target, limit } => user(&client, &target, limit, json),
+        Command::Tui => unreachable!("the terminal UI runs from main"),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            if json {
+                eprintln!("{}", serde_json::json!({ "error": err.to_string() }));
+            } else {
+                eprintln!("error: {err}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print<T: Serialize + Display>(value: &T, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string(value).unwrap());
+    } else {
+        println!("{value}");
+    }
+}
+
+fn print_all<T: Serialize + Display>(values: &[T], json: bool) {
+    if json {
+        println!("{}", serde_json::to_string(values).unwrap());
+    } else {
+        for value in values {
+            println!("{value}");
+        }
+    }
+}
+
+/// Looks up `target`, a track id or any SoundCloud URL.
+fn resolve(client: &Client, target: &str) -> Result<Resource, CliError> {
+    match target.parse::<i64>() {
+        Ok(id) => Ok(Resource::Track(client.track(id)?)),
+        Err(_) => Ok(client.resolve(target)?),
+    }
+}
+
+fn resolve_track(client: &Client, target: &str) -> Result<Track, CliError> {
+    match resolve(client, target)? {
+        Resource::Track(track) => Ok(track),
+        _ => Err(wrong_kind(target, "track")),
+    }
+}
+
+/// Looks up `target`, a playlist id or URL, with all its tracks.
+fn resolve_playlist(client: &Client, target: &str) -> Result<Playlist, CliError> {
+    let id = match target.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => match client.resolve(target)? {
+            Resource::Playlist(playlist) => playlist.id(),
+            _ => return Err(wrong_kind(target, "playlist")),
+        },
+    };
+    Ok(client.playlist(id)?)
+}
+
+fn resolve_user(client: &Client, target: &str) -> Result<User, CliError> {
+    match target.parse::<i64>() {
+        Ok(id) => Ok(client.user(id)?),
+        Err(_) => match client.resolve(target)? {
+            Resource::User(user) => Ok(user),
+            _ => Err(wrong_kind(target, "user")),
+        },
+    }
+}
+
+fn wrong_kind(target: &str, expected: &'static str) -> CliError {
+    CliError::WrongKind {
+        target: String::from(target),
+        expected,
+    }
+}
+
+fn search(client: &Client, query: &str, limit: i64, json: bool) -> Result<(), CliError> {
+    let results = client.search(query, limit, 0)?;
+    let results: Vec<_> = results.collection().iter().map(Info::from).collect();
+    print_all(&results, json);
+    Ok(())
+}
+
+fn info(client: &Client, target: &str, json: bool) -> Result<(), CliError> {
+    let resource = match resolve(client, target)? {
+        // Hydrated, as resolved playlists list most tracks as stubs.
+        Resource::Playlist(playlist) => Resource::Playlist(client.playlist(playlist.id())?),
+        resource => resource,
+    };
+    print(&Info::from(&resource), json);
+    Ok(())
+}
+
+fn play(client: &Client, target: &str, json: bool) -> Result<(), CliError> {
+    let track = resolve_track(client, target)?;
+    let transcoding = track.transcoding(&TranscodingPolicy::mp3())?;
+
+    let (_stream, handle) =
+        OutputStream::try_default().map_err(|err| CliError::Audio(err.to_string()))?;
+    let sink = Sink::try_new(&handle).map_err(|err| CliError::Audio(err.to_string()))?;
+    let decoded = if transcoding.is_hls() {
+        let bytes = client.transcoding_bytes(&transcoding)?;
+        Decoder::new(Cursor::new(bytes)).map(|source| sink.append(source))
+    } else {
+        let stream = client.stream(&transcoding)?;
+        let reader = client.stream_reader(&stream.url(), StreamOptions::default())?;
+        Decoder::new(reader).map(|source| sink.append(source))
+    };
+    decoded.map_err(|err| CliError::Audio(err.to_string()))?;
+
+    print(&TrackInfo::from(&track), json);
+    sink.sleep_until_end();
+    Ok(())
+}
+
+fn download(
+    client: &Client,
+    targets: &[String],
+    dir: Option<PathBuf>,
+    template: Option<String>,
+    json: bool,
+) -> Result<(), CliError> {
+    let mut downloads = Downloads::from_env();
+    if let Some(dir) = dir {
+        downloads.dir = dir;
+    }
+    if let Some(template) = template {
+        downloads.template = FilenameTemplate::new(&template);
+    }
+
+    let mut downloaded = Vec::new();
+    for target in targets {
+        let tracks = match resolve(client, target)? {
+            Resource::Track(track) => vec![track],
+            Resource::Playlist(playlist) => {
+                client.playlist(playlist.id())?.tracks().unwrap_or_default()
+            }
+            Resource::User(_) => return Err(wrong_kind(target, "track or playlist")),
+        };
+        for track in tracks {
+            // Playlists list most tracks as stubs, without their media.
+            let track = if track.is_stub() {
+                client.track(track.id())?
+            } else {
+                track
+            };
+            let title = track.title().unwrap_or_default();
+            let download = client.download_with(
+                &track,
+                &downloads.dir,
+                &TranscodingPolicy::default(),
+                &downloads.template,
+                |progress| {
+                    if let (false, Some(fraction)) = (json, progress.fraction()) {
+                        eprint!("\r{title}: {:>3.0}%", fraction * 100.0);
+                    }
+                },
+            )?;
+            if !json {
+                eprint!("\r");
+            }
+            let tag_error = download.tag_error.map(|err| err.to_string());
+            if let (false, Some(err)) = (json, &tag_error) {
+                eprintln!("warning: {title} saved without tags: {err}");
+            }
+            let item = Downloaded {
+                id: track.id(),
+                path: download.path,
+                tag_error,
+            };
+            if !json {
+                println!("{item}");
+            }
+            downloaded.push(item);
+        }
+    }
+    if json {
+        print_all(&downloaded, json);
+    }
+    Ok(())
+}
+
+fn playlist(client: &Client, target: &str, json: bool) -> Result<(), CliError> {
+    let playlist = resolve_playlist(client, target)?;
+    let playlist = PlaylistInfo::from(&playlist);
+    if json {
+        print(&playlist, json);
+    } else {
+        println!("{playlist}");
+        for track in &playlist.tracks {
+            println!("  {track}");
+        }
+    }
+    Ok(())
+}
+
+fn user(client: &Client, target: &str, limit: i64, json: bool) -> Result<(), CliError> {
+    let user = resolve_user(client, target)?;
+    let tracks = client.user_tracks(user.id(), limit, 0)?;
+    let mut user = UserInfo::from(&user);
+    user.tracks = Some(tracks.collection().iter().map(TrackInfo::from).collect());
+    if json {
+        print(&user, json);
+    } else {
+        println!("{user}");
+        for track in user.tracks.iter().flatten() {
+            println!("  {track}");
+        }
+    }
+    Ok(())
+}
+
+/// A track, user or playlist as printed, one line each in text form.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Info {
+    Track(TrackInfo),
+    User(UserInfo),
+    Playlist(PlaylistInfo),
+}
+
+impl From<&Resource> for Info {
+    fn from(value: &Resource) -> Self {
+        match value {
+            Resource::Track(track) => Self::Track(track.into()),
+            Resource::User(user) => Self::User(user.into()),
+            Resource::Playlist(playlist) => Self::Playlist(playlist.into()),
+        }
+    }
+}
+
+impl Display for Info {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Track(track) => write!(f, "track\t{track}"),
+            Self::User(user) => write!(f, "user\t{user}"),
+            Self::Playlist(playlist) => write!(f, "playlist\t{playlist}"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TrackInfo {
+    id: i64,
+    title: Option<String>,
+    artist: Option<String>,
+    /// Duration in seconds.
+    duration: Option<u64>,
+    genre: Option<String>,
+    tags: Vec<String>,
+    created_at: Option<String>,
+    playback_count: Option<i64>,
+    likes_count: Option<i64>,
+    permalink_url: Option<String>,
+    artwork_url: Option<String>,
+}
+
+impl From<&Track> for TrackInfo {
+    fn from(value: &Track) -> Self {
+        Self {
+            id: value.id(),
+            title: value.title(),
+            artist: value.user().and_then(|user| user.username()),
+            duration: value.duration().map(|duration| duration.as_secs()),
+            genre: value.genre(),
+            tags: value.tag_list(),
+            created_at: value.created_at().map(|created_at| created_at.to_rfc3339()),
+            playback_count: value.playback_count(),
+            likes_count: value.likes_count(),
+            permalink_url: value.permalink_url(),
+            artwork_url: value.artwork_url(),
+        }
+    }
+}
+
+impl Display for TrackInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{} - {}",
+            self.id,
+            self.artist.as_deref().unwrap_or("Unknown"),
+            self.title.as_deref().unwrap_or("Untitled"),
+        )?;
+        if let Some(duration) = self.duration {
+            write!(f, "\t{}", format_duration(Duration::from_secs(duration)))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UserInfo {
+    id: i64,
+    username: Option<String>,
+    followers_count: Option<i64>,
+    followings_count: Option<i64>,
+    created_at: Option<String>,
+    permalink_url: Option<String>,
+    avatar_url: Option<String>,
+    /// Filled in by the `user` command only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracks: Option<Vec<TrackInfo>>,
+}
+
+impl From<&User> for UserInfo {
+    fn from(value: &User) -> Self {
+        Self {
+            id: value.id(),
+            username: value.username(),
+            followers_count: value.followers_count(),
+            followings_count: value.followings_count(),
+            created_at: value.created_at().map(|created_at| created_at.to_rfc3339()),
+            permalink_url: value.permalink_url(),
+            avatar_url: value.avatar_url(),
+            tracks: None,
+        }
+    }
+}
+
+impl Display for UserInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}",
+            self.id,
+            self.username.as_deref().unwrap_or("Unknown"),
+        )?;
+        if let Some(followers) = self.followers_count {
+            write!(f, "\t{followers} followers")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PlaylistInfo {
+    id: i64,
+    title: Option<String>,
+    artist: Option<String>,
+    is_album: bool,
+    track_count: Option<i64>,
+    /// Duration in seconds.
+    duration: Option<u64>,
+    genre: Option<String>,
+    created_at: Option<String>,
+    permalink_url: Option<String>,
+    artwork_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tracks: Vec<TrackInfo>,
+}
+
+impl From<&Playlist> for PlaylistInfo {
+    fn from(value: &Playlist) -> Self {
+        Self {
+            id: value.id(),
+            title: value.title(),
+            artist: value.user().and_then(|user| user.username()),
+            is_album: value.is_album(),
+            track_count: value.track_count(),
+            duration: value.duration().map(|duration| duration.as_secs()),
+            genre: value.genre(),
+            created_at: value.created_at().map(|created_at| created_at.to_rfc3339()),
+            permalink_url: value.permalink_url(),
+            artwork_url: value.artwork_url(),
+            tracks: value
+                .tracks()
+                .unwrap_or_default()
+                .iter()
+                .map(TrackInfo::from)
+                .collect(),
+        }
+    }
+}
+
+impl Display for PlaylistInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{} - {}",
+            self.id,
+            self.artist.as_deref().unwrap_or("Unknown"),
+            self.title.as_deref().unwrap_or("Untitled"),
+        )?;
+        if let Some(track_count) = self.track_count {
+            write!(f, "\t{track_count} tracks")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Downloaded {
+    id: i64,
+    path: PathBuf,
+    /// Why the file was saved without tags, if it was.
+    tag_error: Option<String>,
+}
+
+impl Display for Downloaded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}", self.id, self.path.display())
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,225 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{app::SearchQuery, utils};
+
+/// How many searches are remembered unless the user picks another length.
+const DEFAULT_LIMIT: usize = 50;
+
+/// A search the user ran, and when.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub query: SearchQuery,
+    pub searched_at: SystemTime,
+}
+
+/// Searches the user ran, newest first, and those they pinned to keep.
+/// Saved as JSON after every change, so it outlives the app.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchHistory {
+    entries: Vec<HistoryEntry>,
+    saved: Vec<SearchQuery>,
+    limit: usize,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for SearchHistory {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            saved: Vec::new(),
+            limit: DEFAULT_LIMIT,
+            path: None,
+        }
+    }
+}
+
+impl SearchHistory {
+    /// The history kept in the data directory, or an empty one if there is
+    /// none yet or it can't be read.
+    pub fn open() -> Self {
+        Self::load(&utils::data_dir().join("search_history.json"))
+    }
+
+    pub fn load(path: &Path) -> Self {
+        let history: Self = utils::load_json(path);
+        Self {
+            path: Some(path.to_path_buf()),
+            ..history
+        }
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn saved(&self) -> &[SearchQuery] {
+        &self.saved
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Records `query` as just run, moving it to the front if it was run before.
+    pub fn record(&mut self, query: &SearchQuery) {
+        if query.text.trim().is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry.query != *query);
+        self.entries.insert(
+            0,
+            HistoryEntry {
+                query: query.clone(),
+                searched_at: SystemTime::now(),
+            },
+        );
+        self.entries.truncate(self.limit);
+        self.save();
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+            self.save();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    /// Keeps only the newest `limit` searches from now on.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.entries.truncate(limit);
+        self.save();
+    }
+
+    pub fn is_saved(&self, query: &SearchQuery) -> bool {
+        self.saved.contains(query)
+    }
+
+    /// Pins `query`, filters and all, unless it already is.
+    pub fn pin(&mut self, query: &SearchQuery) {
+        if !self.is_saved(query) {
+            self.saved.push(query.clone());
+            self.save();
+        }
+    }
+
+    pub fn unpin(&mut self, index: usize) {
+        if index < self.saved.len() {
+            self.saved.remove(index);
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            utils::save_json(path, self);
+        }
+    }
+}
+
+/// How long ago `time` was, roughly, e.g. "5 min ago".
+pub fn ago(time: SystemTime) -> String {
+    let seconds = time.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+    match seconds {
+        0..60 => String::from("just now"),
+        60..3600 => format!("{} min ago", seconds / 60),
+        3600..86400 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} d ago", seconds / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use estradiol_soundcloud::search::SearchKind;
+
+    use super::*;
+
+    fn texts(history: &SearchHistory) -> Vec<&str> {
+        history
+            .entries()
+            .iter()
+            .map(|entry| entry.query.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_record_moves_repeats_to_front() {
+        let mut history = SearchHistory::default();
+
+        for text in ["big shot", "spamton", "big shot", "  "] {
+            history.record(&SearchQuery::new(text));
+        }
+
+        assert_eq!(texts(&history), ["big shot", "spamton"]);
+    }
+
+    #[test]
+    fn test_limit_truncates() {
+        let mut history = SearchHistory::default();
+        history.set_limit(3);
+        for text in ["a", "b", "c", "d"] {
+            history.record(&SearchQuery::new(text));
+        }
+        assert_eq!(texts(&history), ["d", "c", "b"]);
+
+        history.set_limit(1);
+
+        assert_eq!(texts(&history), ["d"]);
+        assert_eq!(history.limit(), 1);
+    }
+
+    #[test]
+    fn test_remove_and_unpin_ignore_out_of_range() {
+        let mut history = SearchHistory::default();
+        let query = SearchQuery::new("big shot");
+        history.record(&query);
+        history.pin(&query);
+        history.pin(&query);
+        assert_eq!(history.saved().len(), 1);
+
+        history.remove(1);
+        history.unpin(1);
+        assert_eq!(history.entries().len(), 1);
+        assert!(history.is_saved(&query));
+
+        history.remove(0);
+        history.unpin(0);
+        assert!(history.entries().is_empty());
+        assert!(!history.is_saved(&query));
+    }
+
+    #[test]
+    fn test_history_persists() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("estradiol").join("search_history.json");
+        let query = SearchQuery {
+            kind: SearchKind::Tracks,
+            ..SearchQuery::new("big shot")
+        };
+
+        let mut history = SearchHistory::load(&path);
+        history.set_limit(10);
+        history.record(&SearchQuery::new("spamton"));
+        history.record(&query);
+        history.pin(&query);
+
+        let loaded = SearchHistory::load(&path);
+        assert_eq!(texts(&loaded), ["big shot", "spamton"]);
+        assert_eq!(loaded.entries()[0].query, query);
+        assert_eq!(loaded.saved(), [query]);
+        assert_eq!(loaded.limit(), 10);
+        Ok(())
+    }
+}
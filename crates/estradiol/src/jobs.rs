@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use crate::app_background::BackgroundEvent;
+
+/// Worker threads running slotted jobs: searches, suggestions and track loads.
+const WORKERS: usize = 4;
+/// Worker threads running unslotted jobs such as downloads, kept apart so a
+/// slow download doesn't hold up a search.
+const BULK_WORKERS: usize = 2;
+
+/// Identifies a request from the UI, and every event sent in response to it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    pub fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Jobs of which only the newest is wanted: spawning one cancels the last.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum JobSlot {
+    Search,
+    Suggest,
+    LoadTrack,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Handed to a running job to report progress and check for cancellation.
+///
+/// Tells the UI the job finished when dropped, however the job ended.
+#[derive(Debug)]
+pub struct JobContext {
+    request: RequestId,
+    token: CancelToken,
+    ui_event_tx: Sender<BackgroundEvent>,
+}
+
+impl JobContext {
+    pub fn request(&self) -> RequestId {
+        self.request
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Reports the fraction of the job done, from `0.0` to `1.0`.
+    pub fn progress(&self, progress: f32) {
+        let _ = self.ui_event_tx.send(BackgroundEvent::JobProgress {
+            request: self.request,
+            progress,
+        });
+    }
+}
+
+impl Drop for JobContext {
+    fn drop(&mut self) {
+        let _ = self
+            .ui_event_tx
+            .send(BackgroundEvent::JobFinished(self.request));
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Two fixed sets of worker threads taking jobs in the order they are
+/// spawned: one for slotted jobs, the other for the rest.
+#[derive(Debug)]
+pub struct JobPool {
+    job_tx: Sender<Job>,
+    bulk_tx: Sender<Job>,
+    slots: HashMap<JobSlot, CancelToken>,
+    ui_event_tx: Sender<BackgroundEvent>,
+}
+
+impl JobPool {
+    pub fn new(ui_event_tx: Sender<BackgroundEvent>) -> Self {
+        Self {
+            job_tx: workers(WORKERS),
+            bulk_tx: workers(BULK_WORKERS),
+            slots: HashMap::new(),
+            ui_event_tx,
+        }
+    }
+
+    /// Queues `job` on behalf of `request`, cancelling the job last spawned
+    /// into `slot` if there is one. Jobs without a slot go to the bulk
+    /// workers.
+    pub fn spawn<F>(&mut self, request: RequestId, slot: Option<JobSlot>, label: String, job: F)
+    where
+        F: FnOnce(&JobContext) + Send + 'static,
+    {
+        let token = CancelToken::default();
+        if let Some(slot) = slot {
+            if let Some(stale) = self.slots.insert(slot, token.clone()) {
+                stale.cancel();
+            }
+        }
+
+        let _ = self
+            .ui_event_tx
+            .send(BackgroundEvent::JobStarted { request, label });
+        let context = JobContext {
+            request,
+            token,
+            ui_event_tx: self.ui_event_tx.clone(),
+        };
+        let job_tx = if slot.is_some() {
+            &self.job_tx
+        } else {
+            &self.bulk_tx
+        };
+        let _ = job_tx.send(Box::new(move || {
+            if !context.is_cancelled() {
+                job(&context);
+            }
+        }));
+    }
+
+    /// Cancels whatever job is in `slot`.
+    pub fn cancel(&mut self, slot: JobSlot) {
+        if let Some(token) = self.slots.remove(&slot) {
+            token.cancel();
+        }
+    }
+}
+
+/// Starts `count` workers sharing one queue, returning its sender.
+fn workers(count: usize) -> Sender<Job> {
+    let (job_tx, job_rx) = channel::<Job>();
+    let job_rx = Arc::new(Mutex::new(job_rx));
+    for _ in 0..count {
+        let job_rx = Arc::clone(&job_rx);
+        std::thread::spawn(move || work(&job_rx));
+    }
+    job_tx
+}
+
+fn work(job_rx: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = job_rx.lock().unwrap_or_else(PoisonError::into_inner).recv();
+        match job {
+            // A panicking job still drops its context, so the UI hears it
+            // finished, and the worker lives on to take the next one.
+            Ok(job) => {
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+            }
+            Err(_) => return,
+        }
+    }
+}
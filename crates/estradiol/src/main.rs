@@ -1,38 +1,73 @@
-use std::sync::mpsc::channel;
+use std::{process::ExitCode, sync::mpsc::channel};
 
-use app::{App, UiEvent};
+use app::{App, Request};
 use app_background::BackgroundEvent;
+use clap::Parser;
+use cli::{Cli, Command};
 use egui::Theme;
 
 pub mod anchor_state;
 pub mod app;
 mod app_background;
 pub mod apps;
+mod cli;
+mod history;
+mod jobs;
+mod presence;
+pub mod queue;
+mod settings;
+mod tui;
 pub use app_background::run_background;
 use utils::Channel;
 pub mod utils;
 
-fn main() -> eframe::Result {
-    let (background_event_tx, background_event_rx) = channel::<UiEvent>();
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        None => exit_code(run_gui()),
+        Some(Command::Tui) => {
+            // Background logs would land on the terminal UI's screen.
+            app_background::set_logging(false);
+            exit_code(tui::run(start_background()))
+        }
+        Some(command) => cli::run(command, cli.json),
+    }
+}
+
+fn exit_code<E: std::fmt::Display>(result: Result<(), E>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Starts the background thread that plays audio and talks to SoundCloud,
+/// returning the channel a front-end drives it through.
+fn start_background() -> Channel {
+    let (background_event_tx, background_event_rx) = channel::<Request>();
     let (ui_event_tx, ui_event_rx) = channel::<BackgroundEvent>();
+    std::thread::spawn(run_background(background_event_rx, ui_event_tx));
+    Channel::new(background_event_tx, ui_event_rx)
+}
 
+fn run_gui() -> eframe::Result {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1024.0, 768.0]),
 
         ..Default::default()
     };
 
-    std::thread::spawn(run_background(background_event_rx, ui_event_tx));
+    let channel = start_background();
 
     eframe::run_native(
         "Estradiol",
         options,
         Box::new(|cc| {
             cc.egui_ctx.set_theme(Theme::Dark);
-            Ok(Box::new(App::new(Channel::new(
-                background_event_tx,
-                ui_event_rx,
-            ))))
+            Ok(Box::new(App::new(channel)))
         }),
     )
 }
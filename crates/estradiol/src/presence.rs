@@ -0,0 +1,431 @@
+use std::{
+    sync::mpsc::{channel, RecvTimeoutError, Sender},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+
+use crate::queue::QueueItem;
+
+/// How long to wait before trying Discord again after it went away.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(15);
+/// Longest text Discord accepts in an activity field.
+const MAX_FIELD_CHARS: usize = 128;
+
+/// What the presence shows for the playing track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresenceTrack {
+    pub title: String,
+    pub artist: String,
+    pub artwork_url: Option<String>,
+    pub permalink_url: Option<String>,
+    pub duration: Duration,
+}
+
+impl From<&QueueItem> for PresenceTrack {
+    fn from(item: &QueueItem) -> Self {
+        Self {
+            title: item.title.clone(),
+            artist: item.username.clone(),
+            artwork_url: item.artwork_url.clone(),
+            permalink_url: item.permalink_url.clone(),
+            duration: item.duration,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresenceUpdate {
+    /// Playing `track`, currently at `position`.
+    Playing {
+        track: PresenceTrack,
+        position: Duration,
+    },
+    /// Paused or stopped: nothing is shown.
+    Idle,
+    SetEnabled(bool),
+}
+
+/// A track as published, with the wall-clock times it started and will end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Activity {
+    pub track: PresenceTrack,
+    pub started: SystemTime,
+}
+
+impl Activity {
+    pub fn ends(&self) -> SystemTime {
+        self.started + self.track.duration
+    }
+}
+
+/// A connection presence is published over. Implemented for Discord's IPC
+/// socket, and by fakes standing in for Discord.
+pub trait PresenceIpc {
+    fn connect(&mut self) -> Result<(), String>;
+    fn set_activity(&mut self, activity: &Activity) -> Result<(), String>;
+    fn clear_activity(&mut self) -> Result<(), String>;
+    fn close(&mut self);
+}
+
+/// Discord's IPC socket, for the application with id `client_id`.
+pub struct Discord {
+    client_id: String,
+    client: Option<DiscordIpcClient>,
+}
+
+impl Discord {
+    pub fn new(client_id: &str) -> Self {
+        Self {
+            client_id: String::from(client_id),
+            client: None,
+        }
+    }
+
+    fn client(&mut self) -> Result<&mut DiscordIpcClient, String> {
+        self.client
+            .as_mut()
+            .ok_or_else(|| String::from("not connected"))
+    }
+}
+
+impl PresenceIpc for Discord {
+    fn connect(&mut self) -> Result<(), String> {
+        let mut client = DiscordIpcClient::new(&self.client_id).map_err(|err| err.to_string())?;
+        client.connect().map_err(|err| err.to_string())?;
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn set_activity(&mut self, activity: &Activity) -> Result<(), String> {
+        let track = &activity.track;
+        let details = truncate(&track.title);
+        let state = truncate(&format!("by {}", track.artist));
+        let mut payload = activity::Activity::new()
+            .details(&details)
+            .state(&state)
+            .timestamps(
+                activity::Timestamps::new()
+                    .start(unix_seconds(activity.started))
+                    .end(unix_seconds(activity.ends())),
+            );
+        if let Some(artwork_url) = &track.artwork_url {
+            payload = payload.assets(
+                activity::Assets::new()
+                    .large_image(artwork_url)
+                    .large_text(&details),
+            );
+        }
+        if let Some(permalink_url) = &track.permalink_url {
+            payload = payload.buttons(vec![activity::Button::new(
+                "Listen on SoundCloud",
+                permalink_url,
+            )]);
+        }
+        self.client()?
+            .set_activity(payload)
+            .map_err(|err| err.to_string())
+    }
+
+    fn clear_activity(&mut self) -> Result<(), String> {
+        self.client()?
+            .clear_activity()
+            .map_err(|err| err.to_string())
+    }
+
+    fn close(&mut self) {
+        if let Some(mut client) = self.client.take() {
+            let _ = client.close();
+        }
+    }
+}
+
+/// Keeps the presence in step with playback, reconnecting whenever the IPC
+/// connection is lost, such as when Discord restarts or isn't running yet.
+pub struct Presence<I> {
+    ipc: I,
+    enabled: bool,
+    connected: bool,
+    last_attempt: Option<Instant>,
+    activity: Option<Activity>,
+}
+
+impl<I: PresenceIpc> Presence<I> {
+    pub fn new(ipc: I, enabled: bool) -> Self {
+        Self {
+            ipc,
+            enabled,
+            connected: false,
+            last_attempt: None,
+            activity: None,
+        }
+    }
+
+    pub fn update(&mut self, update: PresenceUpdate) {
+        match update {
+            PresenceUpdate::Playing { track, position } => {
+                self.activity = Some(Activity {
+                    track,
+                    started: SystemTime::now() - position,
+                });
+            }
+            PresenceUpdate::Idle => self.activity = None,
+            PresenceUpdate::SetEnabled(enabled) => {
+                self.enabled = enabled;
+                if !enabled && self.connected {
+                    let _ = self.ipc.clear_activity();
+                    self.disconnect();
+                    return;
+                }
+            }
+        }
+        self.publish();
+    }
+
+    /// Reconnects if the connection was lost and it is time to try again.
+    pub fn tick(&mut self) {
+        if self.enabled && !self.connected {
+            self.publish();
+        }
+    }
+
+    fn publish(&mut self) {
+        if !self.enabled || !self.ensure_connected() {
+            return;
+        }
+        let published = match &self.activity {
+            Some(activity) => self.ipc.set_activity(activity),
+            None => self.ipc.clear_activity(),
+        };
+        if published.is_err() {
+            // Most likely Discord went away; try again on a later tick.
+            self.disconnect();
+        }
+    }
+
+    fn ensure_connected(&mut self) -> bool {
+        if self.connected {
+            return true;
+        }
+        if self
+            .last_attempt
+            .is_some_and(|last_attempt| last_attempt.elapsed() < RECONNECT_INTERVAL)
+        {
+            return false;
+        }
+        self.last_attempt = Some(Instant::now());
+        self.connected = self.ipc.connect().is_ok();
+        self.connected
+    }
+
+    fn disconnect(&mut self) {
+        self.ipc.close();
+        self.connected = false;
+    }
+}
+
+/// The Discord application presence is shown under, from
+/// `ESTRADIOL_DISCORD_CLIENT_ID` at run or build time.
+fn discord_client_id() -> Option<String> {
+    std::env::var("ESTRADIOL_DISCORD_CLIENT_ID")
+        .ok()
+        .or_else(|| option_env!("ESTRADIOL_DISCORD_CLIENT_ID").map(String::from))
+}
+
+/// Whether a Discord application is configured to show presence under.
+pub fn is_available() -> bool {
+    discord_client_id().is_some()
+}
+
+/// Starts publishing presence to Discord on its own thread, so a slow or
+/// missing Discord never holds up playback. Updates are dropped if no
+/// Discord application id is configured.
+pub fn spawn(enabled: bool) -> Sender<PresenceUpdate> {
+    let (update_tx, update_rx) = channel::<PresenceUpdate>();
+    let Some(client_id) = discord_client_id() else {
+        return update_tx;
+    };
+    std::thread::spawn(move || {
+        let mut presence = Presence::new(Discord::new(&client_id), enabled);
+        loop {
+            match update_rx.recv_timeout(RECONNECT_INTERVAL) {
+                Ok(update) => presence.update(update),
+                Err(RecvTimeoutError::Timeout) => presence.tick(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    presence.disconnect();
+                    return;
+                }
+            }
+        }
+    });
+    update_tx
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| {
+        i64::try_from(since.as_secs()).unwrap_or(i64::MAX)
+    })
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_FIELD_CHARS).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Call {
+        Connect,
+        SetActivity(String),
+        ClearActivity,
+        Close,
+    }
+
+    /// Stands in for Discord, recording every call and failing on command.
+    #[derive(Debug, Default)]
+    struct FakeIpc {
+        calls: Vec<Call>,
+        fail_connect: bool,
+        fail_publish: bool,
+    }
+
+    impl PresenceIpc for FakeIpc {
+        fn connect(&mut self) -> Result<(), String> {
+            self.calls.push(Call::Connect);
+            if self.fail_connect {
+                return Err(String::from("connection refused"));
+            }
+            Ok(())
+        }
+
+        fn set_activity(&mut self, activity: &Activity) -> Result<(), String> {
+            self.calls
+                .push(Call::SetActivity(activity.track.title.clone()));
+            if self.fail_publish {
+                return Err(String::from("broken pipe"));
+            }
+            Ok(())
+        }
+
+        fn clear_activity(&mut self) -> Result<(), String> {
+            self.calls.push(Call::ClearActivity);
+            if self.fail_publish {
+                return Err(String::from("broken pipe"));
+            }
+            Ok(())
+        }
+
+        fn close(&mut self) {
+            self.calls.push(Call::Close);
+        }
+    }
+
+    fn playing(title: &str) -> PresenceUpdate {
+        PresenceUpdate::Playing {
+            track: PresenceTrack {
+                title: String::from(title),
+                artist: String::from("Toby Fox"),
+                artwork_url: None,
+                permalink_url: None,
+                duration: Duration::from_secs(180),
+            },
+            position: Duration::from_secs(30),
+        }
+    }
+
+    /// Pretends the last connection attempt was a full interval ago.
+    fn wait_out_reconnect(presence: &mut Presence<FakeIpc>) {
+        presence.last_attempt = presence
+            .last_attempt
+            .and_then(|last_attempt| last_attempt.checked_sub(RECONNECT_INTERVAL));
+    }
+
+    #[test]
+    fn test_connect_failure_keeps_activity() {
+        let ipc = FakeIpc {
+            fail_connect: true,
+            ..FakeIpc::default()
+        };
+        let mut presence = Presence::new(ipc, true);
+
+        presence.update(playing("BIG SHOT"));
+
+        assert_eq!(presence.ipc.calls, vec![Call::Connect]);
+        assert!(!presence.connected);
+        assert_eq!(
+            presence
+                .activity
+                .as_ref()
+                .map(|activity| activity.track.title.as_str()),
+            Some("BIG SHOT")
+        );
+
+        presence.ipc.fail_connect = false;
+        wait_out_reconnect(&mut presence);
+        presence.tick();
+
+        assert!(presence.connected);
+        assert_eq!(
+            presence.ipc.calls[1..],
+            [Call::Connect, Call::SetActivity(String::from("BIG SHOT"))]
+        );
+    }
+
+    #[test]
+    fn test_no_reconnect_before_interval() {
+        let mut presence = Presence::new(FakeIpc::default(), true);
+        presence.update(playing("BIG SHOT"));
+        presence.ipc.fail_publish = true;
+
+        presence.update(playing("Megalovania"));
+
+        assert!(!presence.connected);
+        assert_eq!(presence.ipc.calls.last(), Some(&Call::Close));
+
+        let calls = presence.ipc.calls.len();
+        presence.ipc.fail_publish = false;
+        presence.tick();
+        presence.update(playing("Hopes and Dreams"));
+        assert_eq!(presence.ipc.calls.len(), calls);
+
+        wait_out_reconnect(&mut presence);
+        presence.tick();
+        assert!(presence.connected);
+        assert_eq!(
+            presence.ipc.calls[calls..],
+            [
+                Call::Connect,
+                Call::SetActivity(String::from("Hopes and Dreams"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_idle_clears_activity() {
+        let mut presence = Presence::new(FakeIpc::default(), true);
+        presence.update(playing("BIG SHOT"));
+
+        presence.update(PresenceUpdate::Idle);
+
+        assert!(presence.activity.is_none());
+        assert_eq!(presence.ipc.calls.last(), Some(&Call::ClearActivity));
+        assert!(presence.connected);
+    }
+
+    #[test]
+    fn test_disabling_clears_and_closes() {
+        let mut presence = Presence::new(FakeIpc::default(), true);
+        presence.update(playing("BIG SHOT"));
+
+        presence.update(PresenceUpdate::SetEnabled(false));
+
+        assert!(!presence.connected);
+        assert_eq!(presence.ipc.calls[2..], [Call::ClearActivity, Call::Close]);
+
+        presence.update(playing("Megalovania"));
+        presence.tick();
+        assert_eq!(presence.ipc.calls.len(), 4);
+    }
+}
@@ -0,0 +1,488 @@
+use std::time::Duration;
+
+use estradiol_soundcloud::models::resources::Resource;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueItem {
+    pub id: i64,
+    pub title: String,
+    pub username: String,
+    pub artwork_url: Option<String>,
+    pub permalink_url: Option<String>,
+    pub duration: Duration,
+}
+
+impl From<&Resource> for QueueItem {
+    fn from(resource: &Resource) -> Self {
+        Self {
+            id: resource.id(),
+            title: resource.title().unwrap_or_default(),
+            username: resource
+                .user()
+                .and_then(|user| user.username())
+                .unwrap_or_default(),
+            artwork_url: resource.artwork_url(),
+            permalink_url: resource.permalink_url(),
+            duration: resource
+                .duration()
+                .and_then(|ms| u64::try_from(ms).ok())
+                .map(Duration::from_millis)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+impl std::fmt::Display for RepeatMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepeatMode::Off => write!(f, "Repeat off"),
+            RepeatMode::One => write!(f, "Repeat one"),
+            RepeatMode::All => write!(f, "Repeat all"),
+        }
+    }
+}
+
+/// What the UI needs to draw the queue.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueueSnapshot {
+    pub items: Vec<QueueItem>,
+    pub current: Option<usize>,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+/// Tracks to play, in list order or in a shuffled play order.
+///
+/// `order` always holds every index of `items` exactly once; it is the
+/// identity unless shuffle is on.
+#[derive(Debug, Default)]
+pub struct Queue {
+    items: Vec<QueueItem>,
+    order: Vec<usize>,
+    current: Option<usize>,
+    shuffle: bool,
+    repeat: RepeatMode,
+    rng: fastrand::Rng,
+}
+
+impl Queue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        QueueSnapshot {
+            items: self.items.clone(),
+            current: self.current,
+            shuffle: self.shuffle,
+            repeat: self.repeat,
+        }
+    }
+
+    pub fn current(&self) -> Option<QueueItem> {
+        self.current.map(|index| self.items[index].clone())
+    }
+
+    pub fn enqueue(&mut self, item: QueueItem) {
+        self.items.push(item);
+        let index = self.items.len() - 1;
+        if self.shuffle {
+            let after = self.order_position().map_or(0, |position| position + 1);
+            let position = self.rng.usize(after..=self.order.len());
+            self.order.insert(position, index);
+        } else {
+            self.order.push(index);
+        }
+    }
+
+    /// Inserts `item` to play right after the current track.
+    pub fn play_next(&mut self, item: QueueItem) {
+        let index = self.current.map_or(0, |current| current + 1);
+        let position = self.order_position().map_or(0, |position| position + 1);
+        self.items.insert(index, item);
+        self.remap(|i| Some(if i >= index { i + 1 } else { i }));
+        self.order.insert(position, index);
+        if !self.shuffle {
+            self.order = (0..self.items.len()).collect();
+        }
+    }
+
+    /// Inserts `item` after the current track and makes it current, unless
+    /// it already is.
+    pub fn play_now(&mut self, item: QueueItem) -> QueueItem {
+        // Playing the current track again restarts it rather than queueing a copy.
+        if self.current().as_ref() == Some(&item) {
+            return item;
+        }
+        self.play_next(item);
+        self.advance(false)
+            .expect("the track just inserted follows the current one")
+    }
+
+    /// Removes the item at `index`, returning whether it was the current
+    /// track, in which case the following one becomes current.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index >= self.items.len() {
+            return false;
+        }
+        let was_current = self.current == Some(index);
+        let replacement = if was_current {
+            self.following(false)
+        } else {
+            None
+        };
+
+        self.items.remove(index);
+        self.remap(|i| match i.cmp(&index) {
+            std::cmp::Ordering::Less => Some(i),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(i - 1),
+        });
+
+        if was_current {
+            self.current =
+                replacement
+                    .filter(|&i| i != index)
+                    .map(|i| if i > index { i - 1 } else { i });
+        }
+        was_current
+    }
+
+    pub fn move_item(&mut self, from: usize, to: usize) {
+        if from >= self.items.len() || to >= self.items.len() || from == to {
+            return;
+        }
+        let item = self.items.remove(from);
+        self.items.insert(to, item);
+        self.remap(|i| {
+            Some(if i == from {
+                to
+            } else if from < i && i <= to {
+                i - 1
+            } else if to <= i && i < from {
+                i + 1
+            } else {
+                i
+            })
+        });
+        if !self.shuffle {
+            self.order = (0..self.items.len()).collect();
+        }
+    }
+
+    pub fn jump(&mut self, index: usize) -> Option<QueueItem> {
+        if index >= self.items.len() {
+            return None;
+        }
+        self.current = Some(index);
+        self.current()
+    }
+
+    /// Moves to the next track. `finished` means the current track ended on
+    /// its own, which repeat-one answers with the same track.
+    ///
+    /// Past the end of the queue the current track stays as it is and
+    /// `None` is returned.
+    pub fn advance(&mut self, finished: bool) -> Option<QueueItem> {
+        let next = self.following(finished)?;
+        self.current = Some(next);
+        self.current()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn previous(&mut self) -> Option<QueueItem> {
+        let position = self.order_position()?;
+        let previous = match position.checked_sub(1) {
+            Some(previous) => previous,
+            None if self.repeat == RepeatMode::All => self.order.len() - 1,
+            None => 0,
+        };
+        self.current = Some(self.order[previous]);
+        self.current()
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        self.order = (0..self.items.len()).collect();
+        if !shuffle {
+            return;
+        }
+        // The current track stays first, so turning shuffle on doesn't skip back over it.
+        self.rng.shuffle(&mut self.order);
+        if let Some(position) = self.order_position() {
+            self.order.swap(0, position);
+        }
+    }
+
+    pub fn set_repeat(&mut self, repeat: RepeatMode) {
+        self.repeat = repeat;
+    }
+
+    fn following(&self, finished: bool) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        if finished && self.repeat == RepeatMode::One {
+            return self.current;
+        }
+        // Nothing was playing to finish, so don't start the queue over.
+        if finished && self.current.is_none() {
+            return None;
+        }
+        let next = self.order_position().map_or(0, |position| position + 1);
+        match self.order.get(next) {
+            Some(&index) => Some(index),
+            None if self.repeat == RepeatMode::All => Some(self.order[0]),
+            None => None,
+        }
+    }
+
+    fn order_position(&self) -> Option<usize> {
+        let current = self.current?;
+        self.order.iter().position(|&i| i == current)
+    }
+
+    /// Rewrites item indices after `items` changed shape, dropping those that
+    /// map to `None`.
+    fn remap(&mut self, f: impl Fn(usize) -> Option<usize>) {
+        self.current = self.current.and_then(&f);
+        self.order = self.order.iter().filter_map(|&i| f(i)).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Queue, QueueItem, RepeatMode};
+
+    fn item(id: i64) -> QueueItem {
+        QueueItem {
+            id,
+            title: format!("track {id}"),
+            username: String::from("Toby Fox"),
+            artwork_url: None,
+            permalink_url: None,
+            duration: Duration::from_secs(180),
+        }
+    }
+
+    fn queue(ids: &[i64]) -> Queue {
+        let mut queue = Queue::new();
+        queue.rng = fastrand::Rng::with_seed(7);
+        for &id in ids {
+            queue.enqueue(item(id));
+        }
+        queue
+    }
+
+    fn ids(queue: &Queue) -> Vec<i64> {
+        queue.snapshot().items.iter().map(|item| item.id).collect()
+    }
+
+    fn current_id(queue: &Queue) -> Option<i64> {
+        queue.current().map(|item| item.id)
+    }
+
+    /// Ids in the order `advance` plays them from the current track on.
+    fn play_order(queue: &mut Queue) -> Vec<i64> {
+        let mut played: Vec<_> = current_id(queue).into_iter().collect();
+        while let Some(item) = queue.advance(false) {
+            played.push(item.id);
+        }
+        played
+    }
+
+    #[test]
+    fn test_plays_in_list_order() {
+        let mut queue = queue(&[1, 2, 3]);
+
+        assert_eq!(current_id(&queue), None);
+        assert_eq!(play_order(&mut queue), vec![1, 2, 3]);
+        assert_eq!(current_id(&queue), Some(3));
+    }
+
+    #[test]
+    fn test_next_at_end_keeps_current() {
+        let mut queue = queue(&[1, 2, 3]);
+        queue.jump(2);
+
+        assert_eq!(queue.advance(false), None);
+        assert_eq!(current_id(&queue), Some(3));
+        // The last track then ends on its own without starting over.
+        assert_eq!(queue.advance(true), None);
+        assert_eq!(current_id(&queue), Some(3));
+
+        let mut idle = Queue::new();
+        idle.enqueue(item(1));
+        assert_eq!(idle.advance(true), None);
+        assert_eq!(current_id(&idle), None);
+    }
+
+    #[test]
+    fn test_play_next_follows_current() {
+        let mut queue = queue(&[1, 2, 3]);
+        queue.jump(1);
+
+        queue.play_next(item(4));
+
+        assert_eq!(ids(&queue), vec![1, 2, 4, 3]);
+        assert_eq!(current_id(&queue), Some(2));
+        assert_eq!(play_order(&mut queue), vec![2, 4, 3]);
+    }
+
+    #[test]
+    fn test_play_now_makes_item_current() {
+        let mut queue = queue(&[1, 2, 3]);
+        queue.jump(0);
+
+        assert_eq!(queue.play_now(item(4)).id, 4);
+        assert_eq!(ids(&queue), vec![1, 4, 2, 3]);
+        assert_eq!(queue.snapshot().current, Some(1));
+
+        queue.play_now(item(4));
+        assert_eq!(ids(&queue), vec![1, 4, 2, 3]);
+
+        let mut empty = Queue::new();
+        assert_eq!(empty.play_now(item(5)).id, 5);
+        assert_eq!(empty.snapshot().current, Some(0));
+    }
+
+    #[test]
+    fn test_play_now_while_shuffled() {
+        let mut queue = queue(&[1, 2, 3, 4, 5]);
+        queue.jump(2);
+        queue.set_shuffle(true);
+
+        assert_eq!(queue.play_now(item(6)).id, 6);
+
+        assert_eq!(current_id(&queue), Some(6));
+        let mut played = play_order(&mut queue);
+        assert_eq!(played.len(), 5);
+        played.sort_unstable();
+        assert_eq!(played, vec![1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_remove_earlier_item_keeps_current() {
+        let mut queue = queue(&[1, 2, 3]);
+        queue.jump(2);
+
+        assert!(!queue.remove(0));
+
+        assert_eq!(ids(&queue), vec![2, 3]);
+        assert_eq!(queue.snapshot().current, Some(1));
+        assert_eq!(current_id(&queue), Some(3));
+        assert!(!queue.remove(5));
+    }
+
+    #[test]
+    fn test_remove_current_moves_to_following() {
+        let mut queue = queue(&[1, 2, 3]);
+        queue.jump(1);
+
+        assert!(queue.remove(1));
+        assert_eq!(current_id(&queue), Some(3));
+
+        assert!(queue.remove(1));
+        assert_eq!(current_id(&queue), None);
+        assert_eq!(ids(&queue), vec![1]);
+    }
+
+    #[test]
+    fn test_remove_last_current_wraps_with_repeat_all() {
+        let mut queue = queue(&[1, 2, 3]);
+        queue.set_repeat(RepeatMode::All);
+        queue.jump(2);
+
+        assert!(queue.remove(2));
+
+        assert_eq!(current_id(&queue), Some(1));
+        assert_eq!(queue.snapshot().current, Some(0));
+
+        let mut single = self::queue(&[1]);
+        single.set_repeat(RepeatMode::All);
+        single.jump(0);
+        assert!(single.remove(0));
+        assert_eq!(current_id(&single), None);
+    }
+
+    #[test]
+    fn test_move_item_across_current() {
+        let mut queue = queue(&[1, 2, 3, 4]);
+        queue.jump(1);
+
+        queue.move_item(0, 3);
+        assert_eq!(ids(&queue), vec![2, 3, 4, 1]);
+        assert_eq!(queue.snapshot().current, Some(0));
+        assert_eq!(current_id(&queue), Some(2));
+
+        queue.move_item(3, 0);
+        assert_eq!(ids(&queue), vec![1, 2, 3, 4]);
+        assert_eq!(current_id(&queue), Some(2));
+
+        queue.move_item(1, 3);
+        assert_eq!(ids(&queue), vec![1, 3, 4, 2]);
+        assert_eq!(queue.snapshot().current, Some(3));
+        assert_eq!(queue.advance(false), None);
+    }
+
+    #[test]
+    fn test_shuffle_plays_each_once_from_current() {
+        let mut queue = queue(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        queue.jump(4);
+
+        queue.set_shuffle(true);
+        queue.enqueue(item(9));
+
+        let mut played = play_order(&mut queue);
+        assert_eq!(played[0], 5);
+        assert_eq!(played.len(), 9);
+        played.sort_unstable();
+        assert_eq!(played, (1..=9).collect::<Vec<_>>());
+
+        queue.jump(2);
+        queue.set_shuffle(false);
+        assert_eq!(play_order(&mut queue), vec![3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_repeat_one_repeats_only_finished_track() {
+        let mut queue = queue(&[1, 2]);
+        queue.set_repeat(RepeatMode::One);
+        queue.jump(0);
+
+        assert_eq!(queue.advance(true).map(|item| item.id), Some(1));
+        assert_eq!(queue.advance(false).map(|item| item.id), Some(2));
+        assert_eq!(queue.advance(false), None);
+    }
+
+    #[test]
+    fn test_repeat_all_wraps_both_ways() {
+        let mut queue = queue(&[1, 2, 3]);
+        queue.set_repeat(RepeatMode::All);
+        queue.jump(2);
+
+        assert_eq!(queue.advance(true).map(|item| item.id), Some(1));
+        assert_eq!(queue.previous().map(|item| item.id), Some(3));
+
+        queue.set_repeat(RepeatMode::Off);
+        queue.jump(0);
+        assert_eq!(queue.previous().map(|item| item.id), Some(1));
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+/// What the user picked on the Settings tab, saved as JSON after every change
+/// so the GUI, the terminal UI and the background thread all start with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    discord_presence: bool,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            discord_presence: true,
+            path: None,
+        }
+    }
+}
+
+impl Settings {
+    /// The settings kept in the data directory, or the defaults if there are
+    /// none yet or they can't be read.
+    pub fn open() -> Self {
+        Self::load(&utils::data_dir().join("settings.json"))
+    }
+
+    pub fn load(path: &Path) -> Self {
+        let settings: Self = utils::load_json(path);
+        Self {
+            path: Some(path.to_path_buf()),
+            ..settings
+        }
+    }
+
+    pub fn discord_presence(&self) -> bool {
+        self.discord_presence
+    }
+
+    pub fn set_discord_presence(&mut self, enabled: bool) {
+        self.discord_presence = enabled;
+        self.save();
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            utils::save_json(path, self);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discord_presence_persists() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("estradiol").join("settings.json");
+        assert!(Settings::load(&path).discord_presence());
+
+        Settings::load(&path).set_discord_presence(false);
+
+        assert!(!Settings::load(&path).discord_presence());
+        Ok(())
+    }
+}
@@ -0,0 +1,553 @@
+use std::time::Duration;
+
+use estradiol_soundcloud::models::{collections::Collection, resources::ResourceKind};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Gauge, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    app::{SearchQuery, UiEvent},
+    app_background::{BackgroundEvent, ErrorContext, PlaybackState},
+    apps::jobs::JobsIndicator,
+    jobs::RequestId,
+    presence,
+    queue::{QueueItem, QueueSnapshot, RepeatMode},
+    settings::Settings,
+    utils::Channel,
+};
+
+/// How long to wait for a key before redrawing, so progress keeps moving.
+const TICK: Duration = Duration::from_millis(100);
+/// How far the arrow keys seek.
+const SEEK_STEP: Duration = Duration::from_secs(5);
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Focus {
+    Search,
+    Results,
+    Queue,
+}
+
+/// The terminal front-end, driving the background thread with the same
+/// [`UiEvent`]s as the GUI.
+#[derive(Debug)]
+pub struct Tui {
+    focus: Focus,
+    search: String,
+    results: Vec<QueueItem>,
+    results_state: ListState,
+    /// How many results were fetched, tracks or not, and how many there are.
+    fetched: usize,
+    total: Option<i64>,
+    next_href: Option<String>,
+    loading_more: bool,
+    /// The page that last failed to load, not asked for again until a new
+    /// search runs.
+    more_failed: Option<String>,
+    /// The newest search sent or answered; answers to older ones are stale.
+    latest: Option<RequestId>,
+    searching: bool,
+    queue: QueueSnapshot,
+    queue_state: ListState,
+    position: Duration,
+    duration: Duration,
+    state: PlaybackState,
+    volume: f32,
+    settings: Settings,
+    jobs: JobsIndicator,
+    /// The last error, until the next key press.
+    status: Option<String>,
+    quit: bool,
+    channel: Channel,
+}
+
+/// Runs the terminal UI until the user quits, restoring the terminal after.
+pub fn run(channel: Channel) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = Tui::new(channel).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Tui {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            focus: Focus::Search,
+            search: String::new(),
+            results: Vec::new(),
+            results_state: ListState::default(),
+            fetched: 0,
+            total: None,
+            next_href: None,
+            loading_more: false,
+            more_failed: None,
+            latest: None,
+            searching: false,
+            queue: QueueSnapshot::default(),
+            queue_state: ListState::default(),
+            position: Duration::ZERO,
+            duration: Duration::ZERO,
+            state: PlaybackState::Stopped,
+            volume: 1.0,
+            settings: Settings::open(),
+            jobs: JobsIndicator::new(),
+            status: None,
+            quit: false,
+            channel,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        while !self.quit {
+            for event in self.channel.rx().try_iter() {
+                self.handle_background(event);
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        self.handle_key(key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self, event: UiEvent) -> RequestId {
+        self.channel.send(event)
+    }
+
+    fn handle_background(&mut self, event: BackgroundEvent) {
+        match event {
+            BackgroundEvent::SearchComplete { request, results } => {
+                if self.answer(request) {
+                    self.results.clear();
+                    self.fetched = 0;
+                    self.add_results(&results);
+                    self.results_state
+                        .select((!self.results.is_empty()).then_some(0));
+                }
+            }
+            // Searches only run on Enter here, so nothing asks for suggestions.
+            BackgroundEvent::SearchSuggestions { .. } => {}
+            BackgroundEvent::SearchMoreComplete { search, results } => {
+                if self.latest == Some(search) {
+                    self.add_results(&results);
+                    self.loading_more = false;
+                    self.more_failed = None;
+                }
+            }
+            BackgroundEvent::QueueChanged(snapshot) => {
+                let last = snapshot.items.len().checked_sub(1);
+                let selected = self.queue_state.selected().or(snapshot.current);
+                self.queue_state
+                    .select(selected.zip(last).map(|(index, last)| index.min(last)));
+                self.queue = snapshot;
+            }
+            BackgroundEvent::PlaybackProgress {
+                position,
+                duration,
+                state,
+            } => {
+                self.position = position;
+                self.duration = duration;
+                self.state = state;
+            }
+            BackgroundEvent::Error {
+                request,
+                context,
+                error,
+            } => {
+                match &context {
+                    ErrorContext::Search(_) => {
+                        self.answer(request);
+                    }
+                    ErrorContext::SearchMore { search, next_href }
+                        if self.latest == Some(*search) =>
+                    {
+                        self.loading_more = false;
+                        self.more_failed = Some(next_href.clone());
+                    }
+                    _ => {}
+                }
+                self.status = Some(format!("{context} failed: {error}"));
+            }
+            BackgroundEvent::JobStarted { request, label } => {
+                self.jobs.started(request, label);
+            }
+            BackgroundEvent::JobProgress { request, progress } => {
+                self.jobs.progress(request, progress);
+            }
+            BackgroundEvent::JobFinished(request) => {
+                self.jobs.finished(request);
+            }
+        }
+    }
+
+    /// Records an answer to `request`, returning whether it is still current.
+    fn answer(&mut self, request: RequestId) -> bool {
+        if self.latest.is_some_and(|latest| request < latest) {
+            return false;
+        }
+        self.latest = Some(request);
+        self.searching = false;
+        self.loading_more = false;
+        true
+    }
+
+    /// Adds the tracks of a page of results, remembering where the next is.
+    fn add_results(&mut self, results: &Collection) {
+        let resources = results.collection();
+        self.fetched += resources.len();
+        self.results.extend(
+            resources
+                .iter()
+                .filter(|resource| matches!(resource.kind(), ResourceKind::Track))
+                .map(QueueItem::from),
+        );
+        self.total = results.total_results().or(self.total);
+        self.next_href = results.next_href();
+    }
+
+    /// Asks for the page after the current results, unless one is on its
+    /// way or it failed to load.
+    fn load_more(&mut self) {
+        if self.searching || self.loading_more {
+            return;
+        }
+        let (Some(search), Some(next_href)) = (self.latest, self.next_href.clone()) else {
+            return;
+        };
+        if self.more_failed.as_ref() == Some(&next_href) {
+            return;
+        }
+        self.send(UiEvent::SearchMore { search, next_href });
+        self.loading_more = true;
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        self.status = None;
+        if key.code == KeyCode::Tab {
+            self.focus = match self.focus {
+                Focus::Search => Focus::Results,
+                Focus::Results => Focus::Queue,
+                Focus::Queue => Focus::Search,
+            };
+            return;
+        }
+        match self.focus {
+            Focus::Search => self.search_key(key),
+            Focus::Results => self.results_key(key),
+            Focus::Queue => self.queue_key(key),
+        }
+    }
+
+    fn search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char(c) => self.search.push(c),
+            KeyCode::Backspace => {
+                self.search.pop();
+            }
+            KeyCode::Enter if !self.search.is_empty() => {
+                self.latest =
+                    Some(self.send(UiEvent::SearchSubmit(SearchQuery::new(&self.search))));
+                self.searching = true;
+                self.more_failed = None;
+                self.focus = Focus::Results;
+            }
+            KeyCode::Esc => self.focus = Focus::Results,
+            _ => {}
+        }
+    }
+
+    fn results_key(&mut self, key: KeyEvent) {
+        let selected = self
+            .results_state
+            .selected()
+            .and_then(|index| self.results.get(index))
+            .cloned();
+        match (key.code, selected) {
+            (KeyCode::Up | KeyCode::Char('k'), _) => self.results_state.select_previous(),
+            (KeyCode::Down | KeyCode::Char('j'), _) => {
+                self.results_state.select_next();
+                let last = self.results.len().saturating_sub(1);
+                if self
+                    .results_state
+                    .selected()
+                    .is_some_and(|index| index >= last)
+                {
+                    self.load_more();
+                }
+            }
+            (KeyCode::Enter, Some(item)) => {
+                self.send(UiEvent::PlayTrack(item));
+            }
+            (KeyCode::Char('a'), Some(item)) => {
+                self.send(UiEvent::Enqueue(item));
+            }
+            (KeyCode::Char('i'), Some(item)) => {
+                self.send(UiEvent::PlayNext(item));
+            }
+            (KeyCode::Char('d'), Some(item)) => {
+                self.send(UiEvent::Download(item));
+            }
+            _ => self.playback_key(key),
+        }
+    }
+
+    fn queue_key(&mut self, key: KeyEvent) {
+        let len = self.queue.items.len();
+        let selected = self.queue_state.selected().filter(|&index| index < len);
+        match (key.code, selected) {
+            (KeyCode::Up | KeyCode::Char('k'), _) => self.queue_state.select_previous(),
+            (KeyCode::Down | KeyCode::Char('j'), _) => self.queue_state.select_next(),
+            (KeyCode::Enter, Some(index)) => {
+                self.send(UiEvent::QueueJump(index));
+            }
+            (KeyCode::Delete | KeyCode::Char('D'), Some(index)) => {
+                self.send(UiEvent::QueueRemove(index));
+            }
+            (KeyCode::Char('K'), Some(index)) if index > 0 => {
+                self.send(UiEvent::QueueMove {
+                    from: index,
+                    to: index - 1,
+                });
+                self.queue_state.select(Some(index - 1));
+            }
+            (KeyCode::Char('J'), Some(index)) if index + 1 < len => {
+                self.send(UiEvent::QueueMove {
+                    from: index,
+                    to: index + 1,
+                });
+                self.queue_state.select(Some(index + 1));
+            }
+            _ => self.playback_key(key),
+        }
+    }
+
+    /// Keys that work wherever the search box isn't focused.
+    fn playback_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('/') => self.focus = Focus::Search,
+            KeyCode::Char(' ') => match self.state {
+                PlaybackState::Playing => {
+                    self.send(UiEvent::Pause);
+                }
+                PlaybackState::Paused => {
+                    self.send(UiEvent::Resume);
+                }
+                PlaybackState::Stopped => {}
+            },
+            KeyCode::Char('x') => {
+                self.send(UiEvent::Stop);
+            }
+            KeyCode::Char('n') => {
+                self.send(UiEvent::NextTrack);
+            }
+            KeyCode::Char('p') => {
+                self.send(UiEvent::PreviousTrack);
+            }
+            KeyCode::Char('s') => {
+                self.send(UiEvent::SetShuffle(!self.queue.shuffle));
+            }
+            KeyCode::Char('r') => {
+                let repeat = match self.queue.repeat {
+                    RepeatMode::Off => RepeatMode::All,
+                    RepeatMode::All => RepeatMode::One,
+                    RepeatMode::One => RepeatMode::Off,
+                };
+                self.send(UiEvent::SetRepeat(repeat));
+            }
+            KeyCode::Left if self.state != PlaybackState::Stopped => {
+                self.send(UiEvent::Seek(self.position.saturating_sub(SEEK_STEP)));
+            }
+            KeyCode::Right if self.state != PlaybackState::Stopped => {
+                let position = (self.position + SEEK_STEP).min(self.duration);
+                self.send(UiEvent::Seek(position));
+            }
+            KeyCode::Char('+' | '=') => self.set_volume(self.volume + VOLUME_STEP),
+            KeyCode::Char('-') => self.set_volume(self.volume - VOLUME_STEP),
+            KeyCode::Char('P') if !presence::is_available() => {
+                self.status = Some(String::from(
+                    "Discord presence unavailable: set ESTRADIOL_DISCORD_CLIENT_ID",
+                ));
+            }
+            KeyCode::Char('P') => {
+                let enabled = !self.settings.discord_presence();
+                self.settings.set_discord_presence(enabled);
+                self.send(UiEvent::SetDiscordPresence(enabled));
+                let shown = if enabled { "on" } else { "off" };
+                self.status = Some(format!("Discord presence {shown}"));
+            }
+            _ => {}
+        }
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.send(UiEvent::SetVolume(self.volume));
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search, lists, now_playing, status] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(4),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [results, queue] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(lists);
+
+        self.draw_search(frame, search);
+        self.draw_results(frame, results);
+        self.draw_queue(frame, queue);
+        self.draw_now_playing(frame, now_playing);
+        self.draw_status(frame, status);
+    }
+
+    fn block(&self, title: &str, focus: Focus) -> Block<'static> {
+        let block = Block::bordered().title(format!(" {title} "));
+        if self.focus == focus {
+            block.border_style(Style::new().cyan())
+        } else {
+            block
+        }
+    }
+
+    fn draw_search(&self, frame: &mut Frame, area: Rect) {
+        let title = if self.searching {
+            "Search (searching…)"
+        } else {
+            "Search"
+        };
+        frame.render_widget(
+            Paragraph::new(self.search.as_str()).block(self.block(title, Focus::Search)),
+            area,
+        );
+        if self.focus == Focus::Search {
+            let width = u16::try_from(self.search.chars().count()).unwrap_or(u16::MAX);
+            frame.set_cursor_position((area.x + 1 + width, area.y + 1));
+        }
+    }
+
+    fn draw_results(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<_> = self
+            .results
+            .iter()
+            .map(|item| ListItem::new(format!("{} — {}", item.title, item.username)))
+            .collect();
+        let title = match (self.fetched, self.total) {
+            (0, _) => String::from("Results"),
+            (fetched, Some(total)) => format!("Results ({fetched} of {total})"),
+            (fetched, None) => format!("Results ({fetched})"),
+        };
+        let title = if self.loading_more {
+            format!("{title} (loading…)")
+        } else {
+            title
+        };
+        let list = List::new(items)
+            .block(self.block(&title, Focus::Results))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.results_state);
+    }
+
+    fn draw_queue(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<_> = self
+            .queue
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let line = format!("{} — {}", item.title, item.username);
+                if self.queue.current == Some(index) {
+                    ListItem::new(Line::from(format!("▶ {line}")).bold())
+                } else {
+                    ListItem::new(format!("  {line}"))
+                }
+            })
+            .collect();
+        let mut title = String::from("Queue");
+        if self.queue.shuffle {
+            title.push_str(" · Shuffle");
+        }
+        if self.queue.repeat != RepeatMode::Off {
+            title.push_str(&format!(" · {}", self.queue.repeat));
+        }
+        let list = List::new(items)
+            .block(self.block(&title, Focus::Queue))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.queue_state);
+    }
+
+    fn draw_now_playing(&self, frame: &mut Frame, area: Rect) {
+        let current = self
+            .queue
+            .current
+            .and_then(|index| self.queue.items.get(index));
+        let title = match (current, self.state) {
+            (Some(item), PlaybackState::Playing) => {
+                format!("Playing {} — {}", item.title, item.username)
+            }
+            (Some(item), PlaybackState::Paused) => {
+                format!("Paused {} — {}", item.title, item.username)
+            }
+            _ => String::from("Stopped"),
+        };
+        let ratio = if self.duration.is_zero() {
+            0.0
+        } else {
+            (self.position.as_secs_f64() / self.duration.as_secs_f64()).clamp(0.0, 1.0)
+        };
+        let label = format!(
+            "{} / {} · volume {:.0}%",
+            format_time(self.position),
+            format_time(self.duration),
+            self.volume * 100.0,
+        );
+        let gauge = Gauge::default()
+            .block(Block::bordered().title(format!(" {title} ")))
+            .gauge_style(Style::new().cyan())
+            .ratio(ratio)
+            .label(label);
+        frame.render_widget(gauge, area);
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        let line = if let Some(status) = &self.status {
+            Line::from(status.as_str()).red()
+        } else if let Some((label, progress)) = self.jobs.iter().next() {
+            match progress {
+                Some(progress) => Line::from(format!("{label} {:.0}%", progress * 100.0)),
+                None => Line::from(format!("{label}…")),
+            }
+        } else {
+            Line::from(match self.focus {
+                Focus::Search => "enter search · esc/tab leave",
+                Focus::Results => {
+                    "enter play · a enqueue · i play next · d download · / search · tab queue · q quit"
+                }
+                Focus::Queue => {
+                    "enter jump · D remove · J/K move · space pause · n/p next/prev · s shuffle · r repeat · ←/→ seek · +/- volume · P discord · q quit"
+                }
+            })
+            .dim()
+        };
+        frame.render_widget(Paragraph::new(line), area);
+    }
+}
+
+fn format_time(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
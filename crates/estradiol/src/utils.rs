@@ -1,29 +1,108 @@
-use std::sync::{
-    mpsc::{Receiver, Sender},
-    Arc,
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc,
+    },
 };
 
-use crate::{app::UiEvent, app_background::BackgroundEvent};
+use estradiol_soundcloud::{download::FilenameTemplate, Client};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    app::{Request, UiEvent},
+    app_background::BackgroundEvent,
+    jobs::RequestId,
+};
 
 #[derive(Debug, Clone)]
 pub struct Channel {
-    tx: Arc<Sender<UiEvent>>,
+    tx: Arc<Sender<Request>>,
     rx: Arc<Receiver<BackgroundEvent>>,
 }
 
 impl Channel {
-    pub fn new(tx: Sender<UiEvent>, rx: Receiver<BackgroundEvent>) -> Self {
+    pub fn new(tx: Sender<Request>, rx: Receiver<BackgroundEvent>) -> Self {
         Self {
             tx: Arc::new(tx),
             rx: Arc::new(rx),
         }
     }
 
-    pub fn tx(&self) -> Arc<Sender<UiEvent>> {
-        Arc::clone(&self.tx)
+    /// Sends `event` to the background thread, returning the id its
+    /// responses will carry.
+    pub fn send(&self, event: UiEvent) -> RequestId {
+        let id = RequestId::next();
+        let _ = self.tx.send(Request { id, event });
+        id
     }
 
     pub fn rx(&self) -> Arc<Receiver<BackgroundEvent>> {
         Arc::clone(&self.rx)
     }
 }
+
+/// Where the `client_id` and cached audio are kept between runs.
+pub fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("estradiol")
+}
+
+/// Where what the user keeps, such as their search history, is stored.
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("estradiol")
+}
+
+/// Reads the JSON at `path`, or the defaults if there is none yet or it
+/// can't be read.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Writes `value` to `path` as JSON, creating its directory. Failing to only
+/// loses the changes at exit, so errors are ignored.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) {
+    let Ok(json) = serde_json::to_string_pretty(value) else {
+        return;
+    };
+    let _ = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(path, json));
+}
+
+/// A client sharing its `client_id` with every other run, GUI or CLI.
+pub fn client() -> Client {
+    Client::builder()
+        .client_id_file(cache_dir().join("client_id"))
+        .build()
+}
+
+/// Where downloads go and what they are named, from `ESTRADIOL_DOWNLOAD_DIR`
+/// and `ESTRADIOL_FILENAME_TEMPLATE` if set.
+#[derive(Debug, Clone)]
+pub struct Downloads {
+    pub dir: PathBuf,
+    pub template: FilenameTemplate,
+}
+
+impl Downloads {
+    pub fn from_env() -> Self {
+        let dir = std::env::var_os("ESTRADIOL_DOWNLOAD_DIR")
+            .map(PathBuf::from)
+            .or_else(|| dirs::audio_dir().map(|dir| dir.join("estradiol")))
+            .or_else(|| dirs::download_dir().map(|dir| dir.join("estradiol")))
+            .unwrap_or_else(|| PathBuf::from("downloads"));
+        let template = std::env::var("ESTRADIOL_FILENAME_TEMPLATE").map_or_else(
+            |_| FilenameTemplate::default(),
+            |template| FilenameTemplate::new(&template),
+        );
+        Self { dir, template }
+    }
+}